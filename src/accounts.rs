@@ -12,8 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amp_common::http::{endpoint::Endpoint, Client, HTTPError};
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use amp_common::http::{
+    endpoint::{Empty, Endpoint},
+    HTTPError,
+};
+use futures::Stream;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+
+use crate::client::Client;
+use crate::errors::Error;
+use crate::pagination::paginate;
+use crate::secret::Secret;
+use crate::timestamp::Timestamp;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Account {
@@ -24,15 +40,341 @@ pub struct Account {
     /// The account name
     pub name: String,
     /// When the account was created in Amphitheatre
-    pub created_at: String,
+    pub created_at: Timestamp,
     /// When the account was updated in Amphitheatre
-    pub updated_at: String,
+    pub updated_at: Timestamp,
 }
 
 impl Endpoint for Account {
     type Output = Account;
 }
 
+/// Represents the changes to the profile of the current account,
+/// fields left to `None` are not changed.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct AccountPayload {
+    /// The new account name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The new account email, which has to be verified again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// Represents the payload used to change the password of the current account.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PasswordPayload {
+    /// The password currently in use
+    pub current_password: Secret,
+    /// The new password
+    pub password: Secret,
+}
+
+/// The current use of a resource of the account against its limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Quota {
+    /// The amount currently in use
+    pub used: u64,
+    /// The amount allowed by the plan of the account, `None` if unlimited
+    pub limit: Option<u64>,
+}
+
+impl Quota {
+    /// Returns the amount still available, `None` if unlimited.
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_sub(self.used))
+    }
+
+    /// Returns `true` if nothing more can be used.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == Some(0)
+    }
+
+    /// Returns the used share of the limit, from `0.0` upwards, `None` if unlimited.
+    pub fn utilization(&self) -> Option<f64> {
+        self.limit.map(|limit| match limit {
+            0 if self.used == 0 => 0.0,
+            0 => f64::INFINITY,
+            limit => self.used as f64 / limit as f64,
+        })
+    }
+
+    /// Returns `true` if `amount` more can be used without going over the limit.
+    pub fn allows(&self, amount: u64) -> bool {
+        self.remaining().is_none_or(|remaining| amount <= remaining)
+    }
+}
+
+/// The resources used by the current account against the limits of its plan.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Usage {
+    /// The playbooks currently running
+    pub playbooks: Quota,
+    /// The actors currently running
+    pub actors: Quota,
+    /// The CPU requested by the running actors, in millicores
+    pub cpu: Quota,
+    /// The memory requested by the running actors, in bytes
+    pub memory: Quota,
+    /// The storage used by the account, in bytes
+    pub storage: Quota,
+}
+
+impl Usage {
+    /// Returns every quota along with the name of its resource.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let usage = client.accounts().usage().await.unwrap();
+    ///
+    ///     for (name, quota) in usage.quotas() {
+    ///         if quota.utilization().is_some_and(|u| u >= 0.9) {
+    ///             println!("{name} is at {}/{}", quota.used, quota.limit.unwrap());
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn quotas(&self) -> [(&'static str, &Quota); 5] {
+        [
+            ("playbooks", &self.playbooks),
+            ("actors", &self.actors),
+            ("cpu", &self.cpu),
+            ("memory", &self.memory),
+            ("storage", &self.storage),
+        ]
+    }
+
+    /// Returns the names of the resources with nothing left to use.
+    pub fn exhausted(&self) -> Vec<&'static str> {
+        self.quotas()
+            .into_iter()
+            .filter(|(_, quota)| quota.is_exhausted())
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+struct UsageEndpoint;
+
+impl Endpoint for UsageEndpoint {
+    type Output = Usage;
+}
+
+/// An action recorded in the audit log.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AuditAction {
+    PlaybookCreate,
+    PlaybookUpdate,
+    PlaybookStart,
+    PlaybookStop,
+    PlaybookDelete,
+    ActorSync,
+    /// An action this version of the client does not know about.
+    Other(String),
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AuditAction::PlaybookCreate => "playbook.create",
+            AuditAction::PlaybookUpdate => "playbook.update",
+            AuditAction::PlaybookStart => "playbook.start",
+            AuditAction::PlaybookStop => "playbook.stop",
+            AuditAction::PlaybookDelete => "playbook.delete",
+            AuditAction::ActorSync => "actor.sync",
+            AuditAction::Other(action) => action.as_str(),
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "playbook.create" => AuditAction::PlaybookCreate,
+            "playbook.update" => AuditAction::PlaybookUpdate,
+            "playbook.start" => AuditAction::PlaybookStart,
+            "playbook.stop" => AuditAction::PlaybookStop,
+            "playbook.delete" => AuditAction::PlaybookDelete,
+            "actor.sync" => AuditAction::ActorSync,
+            other => AuditAction::Other(other.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for AuditAction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for AuditAction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(s.parse().unwrap())
+    }
+}
+
+/// The account which performed an audited action.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuditActor {
+    /// The account ID
+    pub id: u64,
+    /// The account email
+    pub email: String,
+    /// The account name
+    pub name: String,
+}
+
+/// The resource an audited action was performed on.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AuditTarget {
+    Playbook {
+        /// The ID of the playbook
+        id: String,
+    },
+    Actor {
+        /// The ID of the playbook the actor belongs to
+        pid: String,
+        /// The name of the actor
+        name: String,
+    },
+    /// A resource this version of the client does not know about.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuditEvent {
+    /// The event ID
+    pub id: u64,
+    /// Who performed the action
+    pub actor: AuditActor,
+    /// What was done
+    pub action: AuditAction,
+    /// What it was done to
+    pub target: AuditTarget,
+    /// The IP address the request came from, if known
+    pub source_ip: Option<String>,
+    /// When the action was performed
+    pub created_at: Timestamp,
+}
+
+/// Narrows the events returned by `Accounts::audit_log`.
+///
+/// # Examples
+///
+/// ```
+/// use amp_client::accounts::{AuditAction, AuditLogFilter};
+///
+/// let filter = AuditLogFilter::new()
+///     .since("2016-01-01T00:00:00Z")
+///     .action(AuditAction::PlaybookDelete)
+///     .playbook("a82abba3-df2f-4608-b1a5-9e058ff80468");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AuditLogFilter {
+    since: Option<Timestamp>,
+    until: Option<Timestamp>,
+    actions: Vec<AuditAction>,
+    target: Option<AuditTarget>,
+    per_page: Option<u32>,
+}
+
+impl AuditLogFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keeps the events at or after `since`.
+    pub fn since<T: Into<Timestamp>>(mut self, since: T) -> Self {
+        self.since = Some(since.into());
+        self
+    }
+
+    /// Only keeps the events before `until`.
+    pub fn until<T: Into<Timestamp>>(mut self, until: T) -> Self {
+        self.until = Some(until.into());
+        self
+    }
+
+    /// Only keeps the events of `action`, can be given several times to keep any of them.
+    pub fn action(mut self, action: AuditAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Only keeps the events performed on the given playbook.
+    pub fn playbook(mut self, pid: &str) -> Self {
+        self.target = Some(AuditTarget::Playbook { id: pid.to_string() });
+        self
+    }
+
+    /// Only keeps the events performed on the given actor.
+    pub fn actor(mut self, pid: &str, name: &str) -> Self {
+        self.target = Some(AuditTarget::Actor {
+            pid: pid.to_string(),
+            name: name.to_string(),
+        });
+        self
+    }
+
+    /// Sets the number of events fetched per request.
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    fn query(&self) -> HashMap<String, String> {
+        let mut query = HashMap::new();
+        if let Some(since) = &self.since {
+            query.insert("since".to_string(), since.to_string());
+        }
+        if let Some(until) = &self.until {
+            query.insert("until".to_string(), until.to_string());
+        }
+        if !self.actions.is_empty() {
+            let actions: Vec<&str> = self.actions.iter().map(AuditAction::as_str).collect();
+            query.insert("action".to_string(), actions.join(","));
+        }
+        match &self.target {
+            Some(AuditTarget::Playbook { id }) => {
+                query.insert("target_type".to_string(), "playbook".to_string());
+                query.insert("target_id".to_string(), id.clone());
+            }
+            Some(AuditTarget::Actor { pid, name }) => {
+                query.insert("target_type".to_string(), "actor".to_string());
+                query.insert("target_id".to_string(), format!("{pid}/{name}"));
+            }
+            Some(AuditTarget::Other) | None => {}
+        }
+        if let Some(per_page) = self.per_page {
+            query.insert("per_page".to_string(), per_page.to_string());
+        }
+        query
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct VerificationParams<'a> {
+    token: &'a str,
+}
+
 /// The Accounts Service handles the account endpoint of the Amphitheatre API.
 ///
 /// See [API Documentation: Account](https://docs.amphitheatre.app/api/account)
@@ -40,7 +382,7 @@ pub struct Accounts<'a> {
     pub client: &'a Client,
 }
 
-impl Accounts<'_> {
+impl<'a> Accounts<'a> {
     /// Retrieves the details about the current authenticated entity used to access the API.
     ///
     /// # Examples
@@ -60,4 +402,111 @@ impl Accounts<'_> {
         let res = self.client.get::<Account>("/me", None).await?;
         Ok(res.data.unwrap())
     }
+
+    /// Updates the name and/or email of the current authenticated account.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::accounts::AccountPayload;
+    /// use amp_client::client::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let payload = AccountPayload {
+    ///         name: Some(String::from("New Name")),
+    ///         ..Default::default()
+    ///     };
+    ///     let account = client.accounts().update_me(payload).await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `payload`: The `AccountPayload` with the fields to change
+    pub async fn update_me(&self, payload: AccountPayload) -> Result<Account, HTTPError> {
+        let res = self
+            .client
+            .patch::<Account, AccountPayload>("/me", &payload)
+            .await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Changes the password of the current authenticated account.
+    ///
+    /// # Arguments
+    ///
+    /// `payload`: The `PasswordPayload` with the current and the new password
+    pub async fn change_password(&self, payload: PasswordPayload) -> Result<u16, HTTPError> {
+        let res = self
+            .client
+            .post::<Empty, PasswordPayload>("/me/password", &payload)
+            .await?;
+        Ok(res.status)
+    }
+
+    /// Sends the verification email to the address of the current authenticated account again.
+    pub async fn send_email_verification(&self) -> Result<u16, HTTPError> {
+        let res = self
+            .client
+            .post::<Empty, Value>("/me/email/verification", &json!(null))
+            .await?;
+        Ok(res.status)
+    }
+
+    /// Verifies the email of the current authenticated account.
+    ///
+    /// # Arguments
+    ///
+    /// `token`: The verification token sent by email
+    pub async fn verify_email(&self, token: &str) -> Result<Account, HTTPError> {
+        let data = VerificationParams { token };
+        let res = self
+            .client
+            .post::<Account, VerificationParams>("/me/email/verify", &data)
+            .await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Retrieves the resources used by the current authenticated account and their limits.
+    ///
+    /// Checking `Usage::playbooks` before `Playbooks::start` avoids a request
+    /// that would be refused for going over the limit.
+    pub async fn usage(&self) -> Result<Usage, HTTPError> {
+        let res = self.client.get::<UsageEndpoint>("/me/usage", None).await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Streams the audit log of the current authenticated account, newest events first.
+    ///
+    /// The events are fetched page by page as the stream is consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::accounts::{AuditAction, AuditLogFilter};
+    /// use amp_client::client::Client;
+    /// use futures::TryStreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let filter = AuditLogFilter::new().action(AuditAction::PlaybookDelete);
+    ///
+    ///     let mut events = Box::pin(client.accounts().audit_log(filter));
+    ///     while let Some(event) = events.try_next().await.unwrap() {
+    ///         println!("{} {} {:?}", event.created_at, event.actor.email, event.target);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `filter`: The `AuditLogFilter` narrowing the events
+    pub fn audit_log(&self, filter: AuditLogFilter) -> impl Stream<Item = Result<AuditEvent, Error>> + 'a {
+        paginate(self.client, "/me/audit_log".to_string(), filter.query())
+    }
 }
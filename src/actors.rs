@@ -12,15 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
-
 use amp_common::http::endpoint::{Empty, JsonValue};
-use amp_common::http::{endpoint::Endpoint, Client, HTTPError};
+use amp_common::http::{endpoint::Endpoint, HTTPError};
 use amp_common::resource::ActorSpec;
 use amp_common::sync::Synchronization;
+use futures::{Stream, TryStreamExt};
+use reqwest::Method;
 use reqwest_eventsource::EventSource;
 use serde_json::Value;
 
+use crate::client::Client;
+use crate::errors::Error;
+use crate::options::{ActorSort, ListOptions};
+use crate::organizations::scoped;
+use crate::pagination::{paginate, Page};
+
 struct ActorEndpoint;
 
 impl Endpoint for ActorEndpoint {
@@ -30,7 +36,7 @@ impl Endpoint for ActorEndpoint {
 struct ActorsEndpoint;
 
 impl Endpoint for ActorsEndpoint {
-    type Output = Vec<ActorSpec>;
+    type Output = Page<ActorSpec>;
 }
 
 /// The Actors Service handles the actors endpoint of the Amphitheatre API.
@@ -38,24 +44,64 @@ impl Endpoint for ActorsEndpoint {
 /// See [API Documentation: playbook](https://docs.amphitheatre.app/api/actor)
 pub struct Actors<'a> {
     pub client: &'a Client,
+    /// The organization the actors belong to, `None` for the personal account
+    pub organization: Option<String>,
 }
 
-impl Actors<'_> {
+impl<'a> Actors<'a> {
+    /// Manages the actors of the given organization instead of the personal account.
+    pub fn in_organization(mut self, org: &str) -> Self {
+        self.organization = Some(org.to_string());
+        self
+    }
+
     /// Lists the actors of playbook.
     ///
     /// # Arguments
     ///
     /// `playbook_id`: The playbook id
-    /// `options`: The `HashMap<String, String>`
-    ///             - Sort: `id`, `label`, `email`
+    /// `options`: The `ListOptions` to sort, filter and paginate the actors
     pub async fn list(
         &self,
         playbook_id: &str,
-        options: Option<HashMap<String, String>>,
+        options: Option<ListOptions<ActorSort>>,
     ) -> Result<Vec<ActorSpec>, HTTPError> {
-        let path = format!("/playbooks/{playbook_id}/actors");
+        let path = self.path(&format!("/playbooks/{playbook_id}/actors"));
+        let options = options.map(|o| o.to_query());
         let res = self.client.get::<ActorsEndpoint>(&path, options).await?;
-        Ok(res.data.unwrap())
+        Ok(res.data.unwrap().items)
+    }
+
+    /// Streams all the actors of playbook, fetching the pages lazily as the stream is consumed.
+    ///
+    /// The `page` of the options is ignored, every page is fetched in turn.
+    ///
+    /// # Arguments
+    ///
+    /// `playbook_id`: The playbook id
+    /// `options`: The `ListOptions` to sort, filter and size the pages
+    pub fn list_stream(
+        &self,
+        playbook_id: &str,
+        options: Option<ListOptions<ActorSort>>,
+    ) -> impl Stream<Item = Result<ActorSpec, Error>> + 'a {
+        let path = self.path(&format!("/playbooks/{playbook_id}/actors"));
+        let query = options.map(|o| o.to_query()).unwrap_or_default();
+        paginate(self.client, path, query)
+    }
+
+    /// Lists all the actors of playbook, following every page.
+    ///
+    /// # Arguments
+    ///
+    /// `playbook_id`: The playbook id
+    /// `options`: The `ListOptions` to sort, filter and size the pages
+    pub async fn list_all(
+        &self,
+        playbook_id: &str,
+        options: Option<ListOptions<ActorSort>>,
+    ) -> Result<Vec<ActorSpec>, Error> {
+        self.list_stream(playbook_id, options).try_collect().await
     }
 
     /// Retrieve a actor
@@ -65,7 +111,7 @@ impl Actors<'_> {
     /// `pid`: The ID of the playbook
     /// `name`: The name of the actor
     pub async fn get(&self, pid: &str, name: &str) -> Result<ActorSpec, HTTPError> {
-        let path = format!("/actors/{pid}/{name}");
+        let path = self.path(&format!("/actors/{pid}/{name}"));
         let res = self.client.get::<ActorEndpoint>(&path, None).await?;
         Ok(res.data.unwrap())
     }
@@ -77,8 +123,8 @@ impl Actors<'_> {
     /// `pid`: The ID of the playbook
     /// `name`: The name of the actor
     pub fn logs(&self, pid: &str, name: &str) -> EventSource {
-        let path = format!("/actors/{pid}/{name}/logs");
-        EventSource::get(self.client.url(&path).expect("Invalid URL"))
+        let path = self.path(&format!("/actors/{pid}/{name}/logs"));
+        EventSource::get(self.client.url(&path))
     }
 
     /// Retrieve actor's info, including environments, volumes...
@@ -88,7 +134,7 @@ impl Actors<'_> {
     /// `pid`: The ID of the playbook
     /// `name`: The name of the actor
     pub async fn info(&self, pid: &str, name: &str) -> Result<Value, HTTPError> {
-        let path = format!("/actors/{pid}/{name}/info");
+        let path = self.path(&format!("/actors/{pid}/{name}/info"));
         let res = self.client.get::<JsonValue>(&path, None).await?;
         Ok(res.data.unwrap())
     }
@@ -100,7 +146,7 @@ impl Actors<'_> {
     /// `pid`: The ID of the playbook
     /// `name`: The name of the actor
     pub async fn stats(&self, pid: &str, name: &str) -> Result<Value, HTTPError> {
-        let path = format!("/actors/{pid}/{name}/stats");
+        let path = self.path(&format!("/actors/{pid}/{name}/stats"));
         let res = self.client.get::<JsonValue>(&path, None).await?;
         Ok(res.data.unwrap())
     }
@@ -112,12 +158,36 @@ impl Actors<'_> {
     /// `pid`: The ID of the playbook
     /// `name`: The name of the actor
     pub async fn sync(&self, pid: &str, name: &str, payload: Synchronization) -> Result<u16, HTTPError> {
-        let path = format!("/actors/{pid}/{name}/sync");
+        let path = self.path(&format!("/actors/{pid}/{name}/sync"));
         let res = self
             .client
             .post::<Empty, Synchronization>(&path, &payload)
             .await?;
 
-        Ok(res.status.as_u16())
+        Ok(res.status)
+    }
+
+    /// Sends a sync batch like `sync`, failing with `Error::Api` when the server
+    /// refuses it, so the caller can tell a rejected batch from an unreachable server.
+    pub(crate) async fn send_sync(
+        &self,
+        pid: &str,
+        name: &str,
+        payload: &Synchronization,
+    ) -> Result<(), Error> {
+        let path = self.path(&format!("/actors/{pid}/{name}/sync"));
+        let res = self
+            .client
+            .raw(Method::POST, &path, |request| request.json(payload))
+            .await?;
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, path: &str) -> String {
+        scoped(self.organization.as_deref(), path)
     }
 }
@@ -0,0 +1,157 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use amp_common::http::HTTPError;
+use futures::future::BoxFuture;
+use tokio::sync::Mutex;
+
+use crate::client::Client;
+use crate::credentials::CredentialStore;
+use crate::oauth::{AccessToken, OAuthRefreshTokenPayload};
+use crate::secret::Secret;
+
+/// Supplies the access token the `Client` authenticates its requests with.
+///
+/// The client asks for the token before every request, so a provider is free
+/// to renew it whenever it needs to.
+pub trait TokenProvider: Send + Sync {
+    /// Returns the token to send with the next request, `None` for anonymous requests.
+    fn token(&self) -> BoxFuture<'_, Result<Option<Secret>, HTTPError>>;
+
+    /// Called once a request sent with `rejected` failed with 401 Unauthorized.
+    ///
+    /// Returns the token to retry the request with, or `None` if there is nothing
+    /// better to offer, in which case the original error is returned to the caller.
+    fn refresh(&self, _rejected: Option<Secret>) -> BoxFuture<'_, Result<Option<Secret>, HTTPError>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+/// A provider for a token that never changes, used by `Client::new`.
+pub struct StaticToken {
+    token: Option<Secret>,
+}
+
+impl StaticToken {
+    pub fn new(token: Option<Secret>) -> Self {
+        Self { token }
+    }
+}
+
+impl TokenProvider for StaticToken {
+    fn token(&self) -> BoxFuture<'_, Result<Option<Secret>, HTTPError>> {
+        Box::pin(async move { Ok(self.token.clone()) })
+    }
+}
+
+/// A provider which renews the `AccessToken` with its refresh token,
+/// both ahead of time when it has expired and when the server rejects it.
+pub struct RefreshingToken {
+    client: Client,
+    client_id: String,
+    client_secret: Option<Secret>,
+    token: Mutex<AccessToken>,
+    store: Option<(Arc<dyn CredentialStore>, String)>,
+}
+
+impl RefreshingToken {
+    /// Creates a provider starting from `token`.
+    ///
+    /// # Arguments
+    ///
+    /// `base_url`: The URL of the Amphitheatre API the token endpoint belongs to
+    /// `client_id`: The client ID of the application the token was issued to
+    /// `client_secret`: The client secret, `None` for public clients
+    /// `token`: The `AccessToken` to start with
+    pub fn new(base_url: &str, client_id: &str, client_secret: Option<Secret>, token: AccessToken) -> Self {
+        Self {
+            client: Client::new(base_url, None),
+            client_id: client_id.to_string(),
+            client_secret,
+            token: Mutex::new(token),
+            store: None,
+        }
+    }
+
+    /// Saves every renewed token in `store` under `server`, so the next run starts
+    /// from the latest refresh token even when the server rotates it.
+    pub fn with_store(mut self, store: Arc<dyn CredentialStore>, server: &str) -> Self {
+        self.store = Some((store, server.to_string()));
+        self
+    }
+
+    /// Returns a copy of the current token, e.g. to persist it after it was renewed.
+    pub async fn current(&self) -> AccessToken {
+        self.token.lock().await.clone()
+    }
+
+    /// Renews `token` in place, returns `false` if it has no refresh token.
+    async fn renew(&self, token: &mut AccessToken) -> Result<bool, HTTPError> {
+        let Some(refresh_token) = token.refresh_token.clone() else {
+            return Ok(false);
+        };
+
+        let payload = OAuthRefreshTokenPayload {
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+            refresh_token: refresh_token.clone(),
+        };
+        let mut renewed = self.client.oauth().refresh(payload).await?;
+
+        // The server may keep the refresh token instead of rotating it.
+        if renewed.refresh_token.is_none() {
+            renewed.refresh_token = Some(refresh_token);
+        }
+        *token = renewed;
+
+        if let Some((store, server)) = &self.store {
+            // Failing to save only costs a new login later, the renewed token is still usable.
+            let _ = store.save(server, token);
+        }
+
+        Ok(true)
+    }
+}
+
+impl TokenProvider for RefreshingToken {
+    fn token(&self) -> BoxFuture<'_, Result<Option<Secret>, HTTPError>> {
+        Box::pin(async move {
+            let mut token = self.token.lock().await;
+            if token.is_expired() {
+                self.renew(&mut token).await?;
+            }
+
+            Ok(Some(token.access_token.clone()))
+        })
+    }
+
+    fn refresh(&self, rejected: Option<Secret>) -> BoxFuture<'_, Result<Option<Secret>, HTTPError>> {
+        Box::pin(async move {
+            let mut token = self.token.lock().await;
+
+            // Another request already renewed the token while this one was in flight.
+            if rejected.as_ref() != Some(&token.access_token) {
+                return Ok(Some(token.access_token.clone()));
+            }
+
+            if self.renew(&mut token).await? {
+                Ok(Some(token.access_token.clone()))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+}
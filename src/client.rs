@@ -12,12 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amp_common::http::Client as HTTPClient;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use amp_common::http::{endpoint::Endpoint, Client as HTTPClient, HTTPError};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::Serialize;
 
 use super::accounts::Accounts;
 use super::actors::Actors;
+use super::auth::{RefreshingToken, StaticToken, TokenProvider};
+use super::credentials::CredentialStore;
+use super::errors::Error;
 use super::oauth::OAuth;
+use super::organizations::Organizations;
 use super::playbooks::Playbooks;
+use super::secret::Secret;
+use super::tokens::Tokens;
+
+/// The user agent of the requests sent by the client.
+const USER_AGENT: &str = concat!("amp-client-rust/", env!("CARGO_PKG_VERSION"));
+
+/// How long to wait for a connection to the server.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for a whole request, response body included.
+const TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Represents the Rust client for the Amphitheatre API
 ///
@@ -37,37 +60,296 @@ use super::playbooks::Playbooks;
 /// }
 /// ```
 pub struct Client {
-    client: HTTPClient,
+    base_url: String,
+    provider: Arc<dyn TokenProvider>,
+    http: Mutex<Connection>,
+    /// The client behind `raw`, kept for its connection pool.
+    raw: reqwest::Client,
+    organization: Option<String>,
+}
+
+/// The HTTP client built for the token it authenticates with.
+struct Connection {
+    token: Option<Secret>,
+    client: Arc<HTTPClient>,
+}
+
+/// The parts of an API response the services make use of.
+pub(crate) struct Response<T> {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The deserialized body, if any.
+    pub data: Option<T>,
 }
 
 impl Client {
     pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self::with_token_provider(base_url, StaticToken::new(token.map(Secret::from)))
+    }
+
+    /// Creates a client which asks `provider` for the token of every request,
+    /// and asks it to refresh the token once when a request is rejected with 401.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::auth::RefreshingToken;
+    /// use amp_client::client::Client;
+    /// use amp_client::oauth::AccessToken;
+    ///
+    /// fn build(token: AccessToken) -> Client {
+    ///     let base_url = "https://cloud.amphitheatre.app";
+    ///     let provider = RefreshingToken::new(base_url, "id", Some("secret".into()), token);
+    ///     Client::with_token_provider(base_url, provider)
+    /// }
+    /// ```
+    pub fn with_token_provider<P: TokenProvider + 'static>(base_url: &str, provider: P) -> Self {
         Self {
-            client: HTTPClient::new(base_url, token).expect("Failed to create HTTP client"),
+            base_url: base_url.to_string(),
+            provider: Arc::new(provider),
+            http: Mutex::new(Connection {
+                token: None,
+                client: Arc::new(connect(base_url, None)),
+            }),
+            raw: raw_client(),
+            organization: None,
         }
     }
+
+    /// Scopes the playbooks and actors services of this client to the given organization.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token).with_organization("acme");
+    ///     let playbooks = client.playbooks().list(None).await.unwrap();
+    /// }
+    /// ```
+    pub fn with_organization(mut self, org: &str) -> Self {
+        self.organization = Some(org.to_string());
+        self
+    }
+
+    /// Returns the organization this client is scoped to, if any.
+    pub fn organization(&self) -> Option<&str> {
+        self.organization.as_deref()
+    }
+
+    /// Creates a client authenticated with the token saved for `base_url` in `store`,
+    /// or an anonymous one if no token was saved yet.
+    ///
+    /// A token saved with a refresh token is renewed with it once it expires, on behalf
+    /// of the public application `client_id`, and the renewed token is saved back.
+    pub fn from_store(
+        base_url: &str,
+        client_id: &str,
+        store: Arc<dyn CredentialStore>,
+    ) -> Result<Self, Error> {
+        let client = match store.load(base_url)? {
+            Some(token) if token.refresh_token.is_some() => {
+                let provider =
+                    RefreshingToken::new(base_url, client_id, None, token).with_store(store, base_url);
+                Self::with_token_provider(base_url, provider)
+            }
+            token => Self::with_token_provider(base_url, StaticToken::new(token.map(|t| t.access_token))),
+        };
+
+        Ok(client)
+    }
 }
 
 impl Client {
     /// Returns the `accounts` services attached to this client
     pub fn accounts(&self) -> Accounts<'_> {
-        Accounts { client: &self.client }
+        Accounts { client: self }
     }
 
     /// Returns the `actors` services attached to this client
     pub fn actors(&self) -> Actors<'_> {
-        Actors { client: &self.client }
+        Actors {
+            client: self,
+            organization: self.organization.clone(),
+        }
     }
 
     /// Returns the `oauth` service attached to this client
     pub fn oauth(&self) -> OAuth<'_> {
-        OAuth { client: &self.client }
+        OAuth { client: self }
+    }
+
+    /// Returns the `organizations` service attached to this client
+    pub fn organizations(&self) -> Organizations<'_> {
+        Organizations { client: self }
     }
 
     /// Returns the `playbooks` service attached to this client
     pub fn playbooks(&self) -> Playbooks<'_> {
-        Playbooks { client: &self.client }
+        Playbooks {
+            client: self,
+            organization: self.organization.clone(),
+        }
     }
+
+    /// Returns the `tokens` service attached to this client
+    pub fn tokens(&self) -> Tokens<'_> {
+        Tokens { client: self }
+    }
+}
+
+impl Client {
+    /// Returns the full URL of the given API path.
+    pub(crate) fn url(&self, path: &str) -> String {
+        let connection = self.http.lock().unwrap();
+        connection.client.url(path).expect("Invalid URL").to_string()
+    }
+
+    pub(crate) async fn get<E: Endpoint>(
+        &self,
+        path: &str,
+        options: Option<HashMap<String, String>>,
+    ) -> Result<Response<E::Output>, HTTPError> {
+        self.send(|http| {
+            let options = options.clone();
+            async move {
+                let res = http.get::<E>(path, options).await?;
+                Ok(Response {
+                    status: res.status.as_u16(),
+                    data: res.data,
+                })
+            }
+        })
+        .await
+    }
+
+    pub(crate) async fn post<E: Endpoint, B: Serialize>(
+        &self,
+        path: &str,
+        data: &B,
+    ) -> Result<Response<E::Output>, HTTPError> {
+        self.send(|http| async move {
+            let res = http.post::<E, B>(path, data).await?;
+            Ok(Response {
+                status: res.status.as_u16(),
+                data: res.data,
+            })
+        })
+        .await
+    }
+
+    pub(crate) async fn patch<E: Endpoint, B: Serialize>(
+        &self,
+        path: &str,
+        data: &B,
+    ) -> Result<Response<E::Output>, HTTPError> {
+        self.send(|http| async move {
+            let res = http.patch::<E, B>(path, data).await?;
+            Ok(Response {
+                status: res.status.as_u16(),
+                data: res.data,
+            })
+        })
+        .await
+    }
+
+    pub(crate) async fn delete<E: Endpoint>(&self, path: &str) -> Result<Response<E::Output>, HTTPError> {
+        self.send(|http| async move {
+            let res = http.delete::<E>(path).await?;
+            Ok(Response {
+                status: res.status.as_u16(),
+                data: res.data,
+            })
+        })
+        .await
+    }
+
+    /// Sends a request directly with reqwest, for the calls which need headers the HTTP
+    /// client does not expose, such as `If-Match` and `ETag`.
+    ///
+    /// `build` completes the authenticated request, it is called again for the retry
+    /// if the token has to be refreshed.
+    pub(crate) async fn raw<F>(
+        &self,
+        method: Method,
+        path: &str,
+        build: F,
+    ) -> Result<reqwest::Response, Error>
+    where
+        F: Fn(RequestBuilder) -> RequestBuilder,
+    {
+        let url = self.url(path);
+        let request = |token: Option<&Secret>| {
+            let mut request = self.raw.request(method.clone(), &url);
+            if let Some(token) = token {
+                request = request.bearer_auth(token.expose());
+            }
+            build(request).send()
+        };
+
+        let token = self.provider.token().await?;
+        let res = request(token.as_ref()).await?;
+        if res.status() != StatusCode::UNAUTHORIZED {
+            return Ok(res);
+        }
+
+        match self.provider.refresh(token).await? {
+            Some(token) => Ok(request(Some(&token)).await?),
+            None => Ok(res),
+        }
+    }
+
+    /// Runs `request` with the token of the provider, refreshing the token
+    /// and retrying once if the server rejected it as unauthorized.
+    async fn send<T, F, Fut>(&self, request: F) -> Result<T, HTTPError>
+    where
+        F: Fn(Arc<HTTPClient>) -> Fut,
+        Fut: Future<Output = Result<T, HTTPError>>,
+    {
+        let token = self.provider.token().await?;
+        let result = request(self.http(token.clone())).await;
+        if !matches!(result, Err(HTTPError::Unauthorized)) {
+            return result;
+        }
+
+        match self.provider.refresh(token).await? {
+            Some(token) => request(self.http(Some(token))).await,
+            None => result,
+        }
+    }
+
+    /// Returns the HTTP client authenticating with `token`, rebuilding it when the token changed.
+    fn http(&self, token: Option<Secret>) -> Arc<HTTPClient> {
+        let mut connection = self.http.lock().unwrap();
+        if connection.token != token {
+            connection.client = Arc::new(connect(&self.base_url, token.as_ref()));
+            connection.token = token;
+        }
+
+        connection.client.clone()
+    }
+}
+
+fn connect(base_url: &str, token: Option<&Secret>) -> HTTPClient {
+    let token = token.map(|t| t.expose().to_string());
+    HTTPClient::new(base_url, token).expect("Failed to create HTTP client")
+}
+
+/// Builds the client behind `Client::raw`, sending JSON requests like the HTTP client does.
+fn raw_client() -> reqwest::Client {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .default_headers(headers)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(TIMEOUT)
+        .build()
+        .expect("Failed to create HTTP client")
 }
 
 #[cfg(test)]
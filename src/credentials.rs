@@ -0,0 +1,142 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::errors::Error;
+use crate::fs::{lock, write_private};
+use crate::oauth::AccessToken;
+
+/// Keeps the `AccessToken`s obtained from `OAuth`, one per server,
+/// so that tokens saved by one tool can be reused by another.
+pub trait CredentialStore: Send + Sync {
+    /// Returns the token saved for `server`, if any.
+    fn load(&self, server: &str) -> Result<Option<AccessToken>, Error>;
+
+    /// Saves `token` for `server`, replacing any previous one.
+    fn save(&self, server: &str, token: &AccessToken) -> Result<(), Error>;
+
+    /// Forgets the token saved for `server`, e.g. on logout.
+    fn remove(&self, server: &str) -> Result<(), Error>;
+}
+
+/// A store keeping the tokens in memory, mostly useful for tests and short-lived tools.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, AccessToken>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CredentialStore for MemoryStore {
+    fn load(&self, server: &str) -> Result<Option<AccessToken>, Error> {
+        Ok(self.entries.lock().unwrap().get(server).cloned())
+    }
+
+    fn save(&self, server: &str, token: &AccessToken) -> Result<(), Error> {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(server.to_string(), token.clone());
+        Ok(())
+    }
+
+    fn remove(&self, server: &str) -> Result<(), Error> {
+        self.entries.lock().unwrap().remove(server);
+        Ok(())
+    }
+}
+
+/// A store keeping the tokens of all servers in a single JSON file.
+///
+/// The file is only readable by the current user (0600 on Unix), and every
+/// change rewrites it atomically so concurrent readers never see a partial file.
+/// Changes hold an advisory lock on the `.lock` file next to it while they read
+/// and rewrite the file, so tools saving tokens at the same time don't drop
+/// each other's changes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use amp_client::client::Client;
+/// use amp_client::credentials::FileStore;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let store = Arc::new(FileStore::new("/home/me/.config/amp/credentials.json"));
+///     let client = Client::from_store("https://cloud.amphitheatre.app", "id", store).unwrap();
+///     let account = client.accounts().me().await.unwrap();
+/// }
+/// ```
+pub struct FileStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileStore {
+    /// Creates a store backed by the file at `path`, which is created on the first save.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> Result<HashMap<String, AccessToken>, Error> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write(&self, entries: &HashMap<String, AccessToken>) -> Result<(), Error> {
+        write_private(&self.path, &serde_json::to_vec_pretty(entries)?)?;
+        Ok(())
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn load(&self, server: &str) -> Result<Option<AccessToken>, Error> {
+        Ok(self.read()?.remove(server))
+    }
+
+    fn save(&self, server: &str, token: &AccessToken) -> Result<(), Error> {
+        let _guard = self.lock.lock().unwrap();
+        let _lock = lock(&self.path)?;
+        let mut entries = self.read()?;
+        entries.insert(server.to_string(), token.clone());
+        self.write(&entries)
+    }
+
+    fn remove(&self, server: &str) -> Result<(), Error> {
+        let _guard = self.lock.lock().unwrap();
+        let _lock = lock(&self.path)?;
+        let mut entries = self.read()?;
+        if entries.remove(server).is_some() {
+            self.write(&entries)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use amp_common::resource::{PlaybookSpec, Preface};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::Error;
+use crate::playbooks::PlaybookPayload;
+
+/// The kind of the documents describing a playbook.
+pub const DOCUMENT_KIND: &str = "Playbook";
+
+/// The version of the document format written by this client, and the latest it reads.
+pub const DOCUMENT_VERSION: u32 = 1;
+
+/// The formats a `PlaybookDocument` can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+impl Format {
+    /// Guesses the format from the extension of `path`: `.json`, or `.yaml`/`.yml`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// A portable definition of a playbook, meant to be checked into a repository
+/// and imported in another environment.
+///
+/// Only the fields describing the playbook are kept, the ones assigned by the
+/// server such as `id` are left out on export and ignored on import. The
+/// characters are exported for reference only, the server resolves them again
+/// from the preface when the playbook is imported.
+///
+/// Every document starts with its `kind` and the `version` of its format, so that
+/// the format can evolve. Documents without them are read as the first version.
+///
+/// # Examples
+///
+/// ```
+/// use amp_client::document::{Format, PlaybookDocument};
+///
+/// let yaml = r#"
+/// kind: Playbook
+/// version: 1
+/// id: a82abba3-df2f-4608-b1a5-9e058ff80468
+/// title: Untitled
+/// preface:
+///   name: amp-example-go
+/// "#;
+///
+/// let document = PlaybookDocument::parse(yaml, Format::Yaml).unwrap();
+/// assert_eq!("Untitled", document.title);
+/// assert!(!document.to_string(Format::Json).unwrap().contains("a82abba3"));
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlaybookDocument {
+    /// The kind of the document, always `DOCUMENT_KIND`
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    /// The version of the document format
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// The title of the playbook
+    pub title: String,
+    /// The description of the playbook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The leading character manifest of the playbook
+    pub preface: Preface,
+    /// The characters of the playbook, as resolved by the server
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub characters: Vec<Value>,
+}
+
+impl PlaybookDocument {
+    /// Builds the document of a playbook retrieved from the server.
+    pub fn from_spec(spec: &PlaybookSpec) -> Result<Self, Error> {
+        // Going through a value drops every field the document doesn't know about.
+        let mut value = serde_json::to_value(spec)?;
+        if let Value::Object(fields) = &mut value {
+            fields.retain(|_, field| !field.is_null());
+        }
+
+        let mut document: Self = serde_json::from_value(value)?;
+        document.description = document.description.filter(|d| !d.is_empty());
+        Ok(document)
+    }
+
+    /// Reads a document written in `format`.
+    ///
+    /// Fails with `Error::Validation` if the document is not a playbook, or was
+    /// written in a newer version of the format than this client knows.
+    pub fn parse(document: &str, format: Format) -> Result<Self, Error> {
+        let document: Self = match format {
+            Format::Json => serde_json::from_str(document)?,
+            Format::Yaml => serde_norway::from_str(document)?,
+        };
+
+        if document.kind != DOCUMENT_KIND {
+            return Err(Error::Validation(format!(
+                "expected a {DOCUMENT_KIND} document, found {:?}",
+                document.kind
+            )));
+        }
+        if document.version == 0 || document.version > DOCUMENT_VERSION {
+            return Err(Error::Validation(format!(
+                "unsupported document version {}, expected {DOCUMENT_VERSION} at most",
+                document.version
+            )));
+        }
+        Ok(document)
+    }
+
+    /// Writes the document in `format`.
+    pub fn to_string(&self, format: Format) -> Result<String, Error> {
+        Ok(match format {
+            Format::Json => serde_json::to_string_pretty(self)?,
+            Format::Yaml => serde_norway::to_string(self)?,
+        })
+    }
+
+    /// Returns the payload creating the playbook described by the document.
+    pub fn into_payload(self) -> PlaybookPayload {
+        PlaybookPayload {
+            title: self.title,
+            description: self.description.unwrap_or_default(),
+            preface: self.preface,
+        }
+    }
+}
+
+fn default_kind() -> String {
+    DOCUMENT_KIND.to_string()
+}
+
+fn default_version() -> u32 {
+    DOCUMENT_VERSION
+}
@@ -0,0 +1,78 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amp_common::http::HTTPError;
+use thiserror::Error;
+
+/// Errors raised by the client helpers that do more than a single API call,
+/// such as touching the local filesystem before or after talking to the server.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    HTTP(#[from] HTTPError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_norway::Error),
+
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("Invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+
+    #[error("Manifest error: {0}")]
+    Manifest(#[from] toml::de::Error),
+
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    #[error("API error ({status}): {body}")]
+    Api {
+        /// The HTTP status code of the response.
+        status: u16,
+        /// The body of the response, as sent by the server.
+        body: String,
+    },
+
+    #[error("Conflict: the resource was changed since it was read")]
+    Conflict {
+        /// The ETag of the current version of the resource, if the server sent it.
+        etag: Option<String>,
+    },
+
+    #[error("OAuth error: {error}")]
+    OAuth {
+        /// The error code returned by the server, e.g. `access_denied`.
+        error: String,
+        /// The human-readable description of the error, if any.
+        description: Option<String>,
+    },
+}
+
+impl Error {
+    /// Turns an unsuccessful response received through `Client::raw` into an error.
+    pub(crate) async fn from_response(res: reqwest::Response) -> Self {
+        let status = res.status().as_u16();
+        match res.text().await {
+            Ok(body) => Error::Api { status, body },
+            Err(err) => err.into(),
+        }
+    }
+}
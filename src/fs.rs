@@ -0,0 +1,74 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use fs4::fs_std::FileExt;
+use tempfile::NamedTempFile;
+
+/// Takes an exclusive advisory lock on the `.lock` file next to `path`, waiting
+/// for other processes holding it, and returns the locked file.
+///
+/// The lock is released when the file is dropped. It only keeps out writers
+/// taking the same lock, readers can still read the file at `path` at any time.
+pub(crate) fn lock(path: &Path) -> io::Result<File> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    let lock = Path::new(&name);
+    fs::create_dir_all(parent(lock))?;
+
+    let mut options = OpenOptions::new();
+    options.create(true).truncate(false).write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(lock)?;
+    file.lock_exclusive()?;
+    Ok(file)
+}
+
+/// Replaces the file at `path` with `contents`, readable by the current user only.
+///
+/// The contents are written to a uniquely named file in the same directory first
+/// and then renamed over the target, so a crash never leaves a truncated file
+/// behind and concurrent writers never share a temporary file.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = parent(path);
+    fs::create_dir_all(parent)?;
+
+    let mut file = NamedTempFile::new_in(parent)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.as_file()
+            .set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+
+    file.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+/// Returns the directory holding `path`, `.` for a bare file name.
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
@@ -16,6 +16,21 @@
 
 pub mod accounts;
 pub mod actors;
+pub mod auth;
 pub mod client;
+pub mod credentials;
+pub mod document;
+pub mod errors;
+mod fs;
+mod loopback;
 pub mod oauth;
+pub mod options;
+pub mod organizations;
+mod pagination;
 pub mod playbooks;
+pub mod repository;
+pub mod scopes;
+pub mod secret;
+pub mod sync;
+pub mod timestamp;
+pub mod tokens;
@@ -0,0 +1,129 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A minimal HTTP listener receiving the OAuth redirect on localhost.
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+const MAX_HEAD_SIZE: usize = 8192;
+
+/// How long a single connection may take to send its request and read the response.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait before accepting connections again after `accept` failed.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+const SUCCESS_PAGE: &str =
+    "<html><body><p>Authorization complete, you can close this window.</p></body></html>";
+
+const INVALID_STATE_PAGE: &str =
+    "<html><body><p>This authorization was not requested by this application.</p></body></html>";
+
+/// Waits for the browser to be redirected to `path` on the listener with the
+/// given `state` and returns the query parameters of that request.
+///
+/// Each connection is handled in its own task, so a browser holding an idle
+/// preconnected socket does not delay the callback. Connections which fail or
+/// stall are dropped, requests for any other path, such as `/favicon.ico`, are
+/// answered with 404 and callbacks with another state with 400, and failures to
+/// accept a connection are retried. Only the callback itself ends the wait.
+pub(crate) async fn callback(listener: &TcpListener, path: &str, state: &str) -> HashMap<String, String> {
+    let (sender, mut received) = mpsc::channel(1);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else {
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                };
+                let sender = sender.clone();
+                let path = path.to_string();
+                let state = state.to_string();
+                tokio::spawn(async move {
+                    let handled = tokio::time::timeout(CONNECTION_TIMEOUT, handle(stream, &path, &state)).await;
+                    if let Ok(Ok(Some(params))) = handled {
+                        let _ = sender.send(params).await;
+                    }
+                });
+            }
+            Some(params) = received.recv() => return params,
+        }
+    }
+}
+
+/// Answers a single connection, returning the query parameters if it was the callback.
+async fn handle(
+    mut stream: TcpStream,
+    path: &str,
+    state: &str,
+) -> io::Result<Option<HashMap<String, String>>> {
+    let Some(target) = read_target(&mut stream).await? else {
+        return Ok(None);
+    };
+
+    let (route, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+    if route != path {
+        respond(&mut stream, "404 Not Found", "").await?;
+        return Ok(None);
+    }
+
+    let params: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    // A callback with another state was not started by us, whatever it carries.
+    if params.get("state").map(String::as_str) != Some(state) {
+        respond(&mut stream, "400 Bad Request", INVALID_STATE_PAGE).await?;
+        return Ok(None);
+    }
+    // The code arrived, a browser closing the window too early must not lose it.
+    let _ = respond(&mut stream, "200 OK", SUCCESS_PAGE).await;
+    Ok(Some(params))
+}
+
+/// Reads the request head and returns the target of a GET request.
+async fn read_target(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut head = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_HEAD_SIZE {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&chunk[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) => Ok(Some(target.to_string())),
+        _ => Ok(None),
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
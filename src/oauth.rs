@@ -12,8 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amp_common::http::{endpoint::Endpoint, Client, HTTPError};
+use std::io::{self, ErrorKind};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use amp_common::http::{
+    endpoint::{Empty, Endpoint},
+    HTTPError,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use url::Url;
+
+use crate::client::Client;
+use crate::errors::Error;
+use crate::loopback;
+use crate::scopes::{Scope, Scopes};
+use crate::secret::Secret;
 
 /// Represents the payload used to exchange this information for the
 /// access token (`AccessToken`).
@@ -22,42 +39,289 @@ pub struct OAuthTokenPayload {
     /// The client ID you received from Amphitheatre when you registered the application.
     pub client_id: String,
     /// The client secret you received from Amphitheatre when you registered the application.
-    pub client_secret: String,
+    pub client_secret: Secret,
+    /// The code acquired in the previous authorization step.
+    pub code: Secret,
+    /// Only used to validate that it matches the original /oauth/authorize, not used to redirect again.
+    pub redirect_uri: String,
+    /// The state content originally passed to /oauth/authorize.
+    pub state: String,
+}
+
+/// Represents the payload used by public clients, which cannot keep a client secret,
+/// to exchange the authorization code together with the PKCE code verifier.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OAuthPkceTokenPayload {
+    /// The client ID you received from Amphitheatre when you registered the application.
+    pub client_id: String,
     /// The code acquired in the previous authorization step.
-    pub code: String,
+    pub code: Secret,
+    /// The code verifier generated along with the authorization URL.
+    pub code_verifier: Secret,
     /// Only used to validate that it matches the original /oauth/authorize, not used to redirect again.
     pub redirect_uri: String,
     /// The state content originally passed to /oauth/authorize.
     pub state: String,
 }
 
+/// Represents the payload used to obtain a new access token with a refresh token.
 #[derive(Debug, Deserialize, Serialize)]
+pub struct OAuthRefreshTokenPayload {
+    /// The client ID you received from Amphitheatre when you registered the application.
+    pub client_id: String,
+    /// The client secret, omitted by public clients which authorized with PKCE.
+    pub client_secret: Option<Secret>,
+    /// The refresh token issued along with the expiring access token.
+    pub refresh_token: Secret,
+}
+
+/// Represents the payload used by machine users, such as deploy bots,
+/// to obtain an access token with their own credentials.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OAuthClientCredentialsPayload {
+    /// The client ID you received from Amphitheatre when you registered the application.
+    pub client_id: String,
+    /// The client secret you received from Amphitheatre when you registered the application.
+    pub client_secret: Secret,
+    /// The scopes to request, the application defaults if `None` or empty.
+    #[serde(default, skip_serializing_if = "crate::scopes::is_unset")]
+    pub scope: Option<Scopes>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 struct OAuthTokenParams {
     grant_type: String,
     client_id: String,
-    client_secret: String,
-    code: String,
-    redirect_uri: String,
-    state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<Secret>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<Secret>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code_verifier: Option<Secret>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_code: Option<Secret>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<Secret>,
+    #[serde(skip_serializing_if = "crate::scopes::is_unset")]
+    scope: Option<Scopes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<String>,
 }
 
-/// Represents an access token containing the token to access the API
+/// Represents the payload used to start the device authorization flow.
 #[derive(Debug, Deserialize, Serialize)]
+pub struct DeviceAuthorizationPayload {
+    /// The client ID you received from Amphitheatre when you registered the application.
+    pub client_id: String,
+    /// The scopes to request, the application defaults if `None` or empty.
+    #[serde(default, skip_serializing_if = "crate::scopes::is_unset")]
+    pub scope: Option<Scopes>,
+}
+
+/// Represents the codes issued to start the device authorization flow.
+///
+/// Show the `user_code` and the `verification_uri` to the user, then wait
+/// for the token with `OAuth::poll_device_token`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeviceAuthorization {
+    /// The code the client polls the token endpoint with.
+    pub device_code: Secret,
+    /// The code the user enters on the verification page.
+    pub user_code: String,
+    /// The page where the user enters the `user_code`.
+    pub verification_uri: String,
+    /// The verification page with the `user_code` already filled in, if supported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_uri_complete: Option<String>,
+    /// The lifetime of the codes in seconds.
+    pub expires_in: u64,
+    /// The minimum number of seconds to wait between polls.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+impl Endpoint for DeviceAuthorization {
+    type Output = Self;
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// The error document returned by the token endpoint.
+#[derive(Debug, Deserialize)]
+struct OAuthErrorBody {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// Builds the URL of the `/oauth/authorize` page the user is sent to.
+///
+/// A random `state` is generated unless one is given, and PKCE is enabled by
+/// default so the resulting code can be exchanged without a client secret.
+#[derive(Debug)]
+pub struct AuthorizationRequest {
+    client_id: String,
+    redirect_uri: Option<String>,
+    scopes: Scopes,
+    state: Option<String>,
+    pkce: bool,
+}
+
+impl AuthorizationRequest {
+    /// Creates a request for the application identified by `client_id`.
+    pub fn new(client_id: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            redirect_uri: None,
+            scopes: Scopes::new(),
+            state: None,
+            pkce: true,
+        }
+    }
+
+    /// Sets the URI the user is redirected to once the application is authorized.
+    pub fn redirect_uri(mut self, redirect_uri: &str) -> Self {
+        self.redirect_uri = Some(redirect_uri.to_string());
+        self
+    }
+
+    /// Adds a scope to request.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scopes.insert(scope);
+        self
+    }
+
+    /// Uses the given `state` instead of a random one.
+    pub fn state(mut self, state: &str) -> Self {
+        self.state = Some(state.to_string());
+        self
+    }
+
+    /// Enables or disables PKCE, which is enabled by default.
+    pub fn pkce(mut self, enabled: bool) -> Self {
+        self.pkce = enabled;
+        self
+    }
+}
+
+/// Represents the authorization URL and the secrets that must be kept until the callback.
+#[derive(Debug)]
+pub struct Authorization {
+    /// The URL to open in the browser of the user.
+    pub url: String,
+    /// The state to compare with the one returned to the redirect URI.
+    pub state: String,
+    /// The PKCE code verifier to send when exchanging the code, if PKCE is enabled.
+    pub code_verifier: Option<Secret>,
+}
+
+/// Generates a cryptographically random, URL-safe string from 32 bytes of entropy.
+fn random_token() -> String {
+    let bytes: [u8; 32] = rand::random();
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Derives the S256 PKCE code challenge of the given code verifier.
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// How long before its expiry an access token is already treated as expired, so that
+/// it is renewed before a request carrying it could reach the server too late.
+pub const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Represents an access token containing the token to access the API
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AccessToken {
     /// The token you can use to authenticate.
-    pub access_token: String,
+    pub access_token: Secret,
     /// The account ID in Amphitheatre this token belongs to.
     pub account_id: u64,
-    /// The token scope (not used for now).
-    pub scope: Option<String>,
+    /// The scopes granted to the token.
+    pub scope: Option<Scopes>,
     /// The token type.
     pub token_type: String,
+    /// The token used to obtain a new access token once this one expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<Secret>,
+    /// The lifetime of the access token in seconds, `None` if it does not expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+    /// When the token was issued, in seconds since the Unix epoch.
+    /// Filled in with the time of the response when the server does not send it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+}
+
+impl AccessToken {
+    /// Returns when the access token expires, or `None` if it does not expire.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        let created_at = self.created_at?;
+        let expires_in = self.expires_in?;
+        Some(UNIX_EPOCH + Duration::from_secs(created_at.saturating_add(expires_in)))
+    }
+
+    /// Returns `true` if the access token has expired or expires within `EXPIRY_MARGIN`.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at()
+            .is_some_and(|at| at <= SystemTime::now() + EXPIRY_MARGIN)
+    }
+
+    /// Returns `true` if the token was granted `scope`, so callers can fail fast
+    /// before making a request the server would reject.
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scope.as_ref().is_some_and(|scopes| scopes.has(scope))
+    }
 }
 
 impl Endpoint for AccessToken {
     type Output = Self;
 }
 
+/// Represents the state of a token as reported by the introspection endpoint.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TokenIntrospection {
+    /// Whether the token is currently active, i.e. neither expired nor revoked.
+    pub active: bool,
+    /// The scopes granted to the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<Scopes>,
+    /// The account ID in Amphitheatre this token belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<u64>,
+    /// The client ID of the application the token was issued to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// The token type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    /// When the token expires, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    /// When the token was issued, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+}
+
+impl TokenIntrospection {
+    /// Returns when the token expires, or `None` if it does not expire.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.exp.map(|exp| UNIX_EPOCH + Duration::from_secs(exp))
+    }
+}
+
+impl Endpoint for TokenIntrospection {
+    type Output = Self;
+}
+
+#[derive(Debug, Serialize)]
+struct TokenParams<'a> {
+    token: &'a str,
+}
+
 /// The Oauth Service is used to request access to the API
 ///
 /// See [API Documentation: oauth](https://docs.amphitheatre.app/api/oauth/)
@@ -81,8 +345,8 @@ impl OAuth<'_> {
     ///     let client = Client::new("https://cloud.amphitheatre.app", token);
     ///     let payload = OAuthTokenPayload {
     ///         client_id: "id".to_string(),
-    ///         client_secret: "secret".to_string(),
-    ///         code: "code".to_string(),
+    ///         client_secret: "secret".into(),
+    ///         code: "code".into(),
     ///         redirect_uri: "/redirect_uri".to_string(),
     ///         state: "state".to_string(),
     ///     };
@@ -98,20 +362,473 @@ impl OAuth<'_> {
         &self,
         payload: OAuthTokenPayload,
     ) -> Result<AccessToken, HTTPError> {
-        let path = "/oauth/access_token";
         let data = OAuthTokenParams {
             grant_type: "authorization_code".to_string(),
             client_id: payload.client_id,
+            client_secret: Some(payload.client_secret),
+            code: Some(payload.code),
+            redirect_uri: Some(payload.redirect_uri),
+            state: Some(payload.state),
+            ..Default::default()
+        };
+
+        self.request_token(data).await
+    }
+
+    /// Builds the `/oauth/authorize` URL for the given request, generating the
+    /// `state` and, when PKCE is enabled, the code verifier and its challenge.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::oauth::AuthorizationRequest;
+    ///
+    /// let client = Client::new("https://cloud.amphitheatre.app", None);
+    /// let request = AuthorizationRequest::new("id").redirect_uri("http://localhost:8080/callback");
+    /// let authorization = client.oauth().authorize_url(request).unwrap();
+    ///
+    /// println!("Open {} in your browser", authorization.url);
+    /// ```
+    ///
+    /// # Attributes
+    ///
+    /// `request`: The `AuthorizationRequest` describing the application and the scopes.
+    pub fn authorize_url(&self, request: AuthorizationRequest) -> Result<Authorization, Error> {
+        let mut url = Url::parse(&self.client.url("/oauth/authorize"))?;
+
+        let state = request.state.unwrap_or_else(random_token);
+        let code_verifier = request.pkce.then(|| Secret::new(random_token()));
+
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("response_type", "code");
+            query.append_pair("client_id", &request.client_id);
+            if let Some(redirect_uri) = &request.redirect_uri {
+                query.append_pair("redirect_uri", redirect_uri);
+            }
+            if !request.scopes.is_empty() {
+                query.append_pair("scope", &request.scopes.to_string());
+            }
+            query.append_pair("state", &state);
+            if let Some(code_verifier) = &code_verifier {
+                query.append_pair("code_challenge", &code_challenge(code_verifier.expose()));
+                query.append_pair("code_challenge_method", "S256");
+            }
+        }
+
+        Ok(Authorization {
+            url: url.to_string(),
+            state,
+            code_verifier,
+        })
+    }
+
+    /// Exchange the authorization code obtained with PKCE for an access token,
+    /// sending the code verifier instead of a client secret.
+    ///
+    /// # Attributes
+    ///
+    /// `payload`: The `OAuthPkceTokenPayload` with the necessary information to get the access token.
+    pub async fn exchange_authorization_with_verifier(
+        &self,
+        payload: OAuthPkceTokenPayload,
+    ) -> Result<AccessToken, HTTPError> {
+        let data = OAuthTokenParams {
+            grant_type: "authorization_code".to_string(),
+            client_id: payload.client_id,
+            code: Some(payload.code),
+            code_verifier: Some(payload.code_verifier),
+            redirect_uri: Some(payload.redirect_uri),
+            state: Some(payload.state),
+            ..Default::default()
+        };
+
+        self.request_token(data).await
+    }
+
+    /// Obtain a new access token with the refresh token of an expiring one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::oauth::OAuthRefreshTokenPayload;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = Client::new("https://cloud.amphitheatre.app", None);
+    ///     let payload = OAuthRefreshTokenPayload {
+    ///         client_id: "id".to_string(),
+    ///         client_secret: Some("secret".into()),
+    ///         refresh_token: "refresh-token".into(),
+    ///     };
+    ///
+    ///     let access_token = client.oauth().refresh(payload).await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Attributes
+    ///
+    /// `payload`: The `OAuthRefreshTokenPayload` with the refresh token.
+    pub async fn refresh(&self, payload: OAuthRefreshTokenPayload) -> Result<AccessToken, HTTPError> {
+        let data = OAuthTokenParams {
+            grant_type: "refresh_token".to_string(),
+            client_id: payload.client_id,
             client_secret: payload.client_secret,
-            code: payload.code,
-            redirect_uri: payload.redirect_uri,
-            state: payload.state,
+            refresh_token: Some(payload.refresh_token),
+            ..Default::default()
+        };
+
+        self.request_token(data).await
+    }
+
+    /// Obtain an access token with the credentials of the application itself,
+    /// for machine users which cannot complete an authorization-code flow.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::oauth::OAuthClientCredentialsPayload;
+    /// use amp_client::scopes::Scope;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = Client::new("https://cloud.amphitheatre.app", None);
+    ///     let payload = OAuthClientCredentialsPayload {
+    ///         client_id: "id".to_string(),
+    ///         client_secret: "secret".into(),
+    ///         scope: Some(Scope::PlaybooksWrite.into()),
+    ///     };
+    ///
+    ///     let access_token = client.oauth().client_credentials(payload).await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Attributes
+    ///
+    /// `payload`: The `OAuthClientCredentialsPayload` with the credentials of the application.
+    pub async fn client_credentials(
+        &self,
+        payload: OAuthClientCredentialsPayload,
+    ) -> Result<AccessToken, HTTPError> {
+        let data = OAuthTokenParams {
+            grant_type: "client_credentials".to_string(),
+            client_id: payload.client_id,
+            client_secret: Some(payload.client_secret),
+            scope: payload.scope,
+            ..Default::default()
         };
 
+        self.request_token(data).await
+    }
+
+    /// Starts the device authorization flow, for clients which cannot open a browser
+    /// such as CLIs on headless machines or over SSH.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::oauth::DeviceAuthorizationPayload;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = Client::new("https://cloud.amphitheatre.app", None);
+    ///     let payload = DeviceAuthorizationPayload {
+    ///         client_id: "id".to_string(),
+    ///         scope: None,
+    ///     };
+    ///
+    ///     let authorization = client.oauth().authorize_device(payload).await.unwrap();
+    ///     println!("Open {} and enter {}", authorization.verification_uri, authorization.user_code);
+    ///
+    ///     let access_token = client.oauth().poll_device_token("id", &authorization).await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Attributes
+    ///
+    /// `payload`: The `DeviceAuthorizationPayload` identifying the application.
+    pub async fn authorize_device(
+        &self,
+        payload: DeviceAuthorizationPayload,
+    ) -> Result<DeviceAuthorization, HTTPError> {
         let res = self
             .client
-            .post::<AccessToken, OAuthTokenParams>(path, &data)
+            .post::<DeviceAuthorization, DeviceAuthorizationPayload>("/oauth/authorize_device", &payload)
             .await?;
         Ok(res.data.unwrap())
     }
+
+    /// Polls the token endpoint until the user approved the device authorization.
+    ///
+    /// Waits `interval` seconds between polls, backing off by five more seconds
+    /// each time the server answers `slow_down`, and doubling the wait, up to
+    /// `MAX_BACKOFF`, when it fails. Fails with `Error::OAuth` when the user denies
+    /// the request or the codes expire before being approved, and with
+    /// `Error::Serialization` when the server sends a token it cannot read.
+    ///
+    /// # Attributes
+    ///
+    /// `client_id`: The client ID used to start the flow.
+    /// `authorization`: The `DeviceAuthorization` returned by `authorize_device`.
+    pub async fn poll_device_token(
+        &self,
+        client_id: &str,
+        authorization: &DeviceAuthorization,
+    ) -> Result<AccessToken, Error> {
+        let data = OAuthTokenParams {
+            grant_type: "urn:ietf:params:oauth:grant-type:device_code".to_string(),
+            client_id: client_id.to_string(),
+            device_code: Some(authorization.device_code.clone()),
+            ..Default::default()
+        };
+
+        let deadline = Instant::now() + Duration::from_secs(authorization.expires_in);
+        let mut interval = Duration::from_secs(authorization.interval);
+
+        loop {
+            tokio::time::sleep(interval.min(deadline.saturating_duration_since(Instant::now()))).await;
+            if Instant::now() >= deadline {
+                return Err(Error::OAuth {
+                    error: "expired_token".to_string(),
+                    description: Some("The device code expired before it was approved".to_string()),
+                });
+            }
+
+            // The pending states come back as 400 responses, so read the body ourselves.
+            let res = self
+                .client
+                .raw(Method::POST, "/oauth/access_token", |request| request.json(&data))
+                .await?;
+            let status = res.status();
+            let body = res.bytes().await?;
+
+            if status.is_server_error() {
+                interval = backoff(interval);
+                continue;
+            }
+            if status.is_success() {
+                return Ok(issued(serde_json::from_slice(&body)?));
+            }
+
+            let Ok(err) = serde_json::from_slice::<OAuthErrorBody>(&body) else {
+                return Err(Error::Api {
+                    status: status.as_u16(),
+                    body: String::from_utf8_lossy(&body).into_owned(),
+                });
+            };
+            match err.error.as_str() {
+                "authorization_pending" => {}
+                "slow_down" => interval += Duration::from_secs(5),
+                _ => {
+                    return Err(Error::OAuth {
+                        error: err.error,
+                        description: err.error_description,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Logs in through the browser, receiving the authorization code on a temporary
+    /// listener bound to localhost.
+    ///
+    /// The redirect URI of `request` is replaced with the one of the listener, and the
+    /// authorization URL is handed to `open`, which is expected to open it in a browser.
+    /// Callbacks whose `state` doesn't match the request are refused and the listener
+    /// keeps waiting. Once the callback arrives, the code is exchanged for an
+    /// `AccessToken` and the listener is shut down. Fails with an `ErrorKind::TimedOut`
+    /// I/O error if no callback arrives within `timeout`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use amp_client::client::Client;
+    /// use amp_client::oauth::AuthorizationRequest;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = Client::new("https://cloud.amphitheatre.app", None);
+    ///     let request = AuthorizationRequest::new("id");
+    ///
+    ///     let access_token = client
+    ///         .oauth()
+    ///         .authorize_with_loopback(request, None, Duration::from_secs(300), |url| {
+    ///             println!("Open {url} in your browser");
+    ///         })
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Attributes
+    ///
+    /// `request`: The `AuthorizationRequest` describing the application and the scopes.
+    /// `client_secret`: The client secret, `None` for public clients using PKCE.
+    /// `timeout`: How long to wait for the user to complete the authorization.
+    /// `open`: Called with the authorization URL to show to the user.
+    pub async fn authorize_with_loopback<F: FnOnce(&str)>(
+        &self,
+        request: AuthorizationRequest,
+        client_secret: Option<Secret>,
+        timeout: Duration,
+        open: F,
+    ) -> Result<AccessToken, Error> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let redirect_uri = format!("http://127.0.0.1:{}/callback", listener.local_addr()?.port());
+
+        let client_id = request.client_id.clone();
+        let authorization = self.authorize_url(request.redirect_uri(&redirect_uri))?;
+        open(&authorization.url);
+
+        let callback = loopback::callback(&listener, "/callback", &authorization.state);
+        let params = tokio::time::timeout(timeout, callback)
+            .await
+            .map_err(|_| io::Error::new(ErrorKind::TimedOut, "Timed out waiting for the authorization"))?;
+        drop(listener);
+
+        if let Some(error) = params.get("error") {
+            return Err(Error::OAuth {
+                error: error.clone(),
+                description: params.get("error_description").cloned(),
+            });
+        }
+        let Some(code) = params.get("code") else {
+            return Err(Error::OAuth {
+                error: "invalid_request".to_string(),
+                description: Some("The callback is missing the authorization code".to_string()),
+            });
+        };
+
+        let data = OAuthTokenParams {
+            grant_type: "authorization_code".to_string(),
+            client_id,
+            client_secret,
+            code: Some(Secret::new(code.as_str())),
+            code_verifier: authorization.code_verifier,
+            redirect_uri: Some(redirect_uri),
+            state: Some(authorization.state),
+            ..Default::default()
+        };
+
+        Ok(self.request_token(data).await?)
+    }
+
+    /// Revoke an access or refresh token, e.g. after it leaked or its owner left.
+    ///
+    /// Revoking a token which is unknown or already revoked is not an error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     client.oauth().revoke("LEAKED_TOKEN").await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Attributes
+    ///
+    /// `token`: The token to revoke.
+    pub async fn revoke(&self, token: &str) -> Result<u16, HTTPError> {
+        let data = TokenParams { token };
+        let res = self
+            .client
+            .post::<Empty, TokenParams>("/oauth/revoke", &data)
+            .await?;
+        Ok(res.status)
+    }
+
+    /// Retrieve whether a token is still active, along with its scopes, account and expiry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let introspection = client.oauth().introspect("SOME_TOKEN").await.unwrap();
+    ///
+    ///     if !introspection.active {
+    ///         println!("The token was revoked or has expired");
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Attributes
+    ///
+    /// `token`: The token to inspect.
+    pub async fn introspect(&self, token: &str) -> Result<TokenIntrospection, HTTPError> {
+        let data = TokenParams { token };
+        let res = self
+            .client
+            .post::<TokenIntrospection, TokenParams>("/oauth/introspect", &data)
+            .await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Posts the grant to the token endpoint, recording when the token was issued.
+    async fn request_token(&self, data: OAuthTokenParams) -> Result<AccessToken, HTTPError> {
+        let res = self
+            .client
+            .post::<AccessToken, OAuthTokenParams>("/oauth/access_token", &data)
+            .await?;
+        Ok(issued(res.data.unwrap()))
+    }
+}
+
+/// The longest wait between two polls of the token endpoint after a server error.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Doubles the wait between two polls of the token endpoint, waiting at least a second
+/// and at most `MAX_BACKOFF`, unless the server already asked to wait longer.
+fn backoff(interval: Duration) -> Duration {
+    (interval * 2)
+        .clamp(Duration::from_secs(1), MAX_BACKOFF)
+        .max(interval)
+}
+
+/// Fills in when the token was issued if the server did not send it.
+fn issued(mut token: AccessToken) -> AccessToken {
+    if token.created_at.is_none() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        token.created_at = Some(now.as_secs());
+    }
+
+    token
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{backoff, code_challenge, MAX_BACKOFF};
+
+    #[test]
+    fn derives_the_s256_code_challenge() {
+        // The example of RFC 7636, appendix B.
+        assert_eq!(
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM",
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk")
+        );
+    }
+    #[test]
+    fn backs_off_up_to_a_limit() {
+        assert_eq!(Duration::from_secs(1), backoff(Duration::ZERO));
+        assert_eq!(Duration::from_secs(10), backoff(Duration::from_secs(5)));
+        assert_eq!(MAX_BACKOFF, backoff(Duration::from_secs(40)));
+        assert_eq!(Duration::from_secs(90), backoff(Duration::from_secs(90)));
+    }
 }
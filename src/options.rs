@@ -0,0 +1,243 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+/// A field a list endpoint can sort by.
+pub trait SortField {
+    /// Returns the name of the field in the query string.
+    fn as_str(&self) -> &'static str;
+}
+
+/// The direction of a sort.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl Order {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Order::Asc => "asc",
+            Order::Desc => "desc",
+        }
+    }
+}
+
+/// The fields playbooks can be sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybookSort {
+    Id,
+    Title,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for PlaybookSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PlaybookSort::Id => "id",
+            PlaybookSort::Title => "title",
+            PlaybookSort::CreatedAt => "created_at",
+            PlaybookSort::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+/// The fields actors can be sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActorSort {
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for ActorSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ActorSort::Name => "name",
+            ActorSort::CreatedAt => "created_at",
+            ActorSort::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+/// The options of a list endpoint, sent as the query string.
+///
+/// The sort fields and filters are typed for each endpoint, `param` sets any
+/// other query parameter as is, for those this version of the client lacks.
+///
+/// # Examples
+///
+/// ```no_run
+/// use amp_client::client::Client;
+/// use amp_client::options::{ListOptions, Order, PlaybookSort};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let token = Some(String::from("AUTH_TOKEN"));
+///     let client = Client::new("https://cloud.amphitheatre.app", token);
+///     let options = ListOptions::new()
+///         .sort(PlaybookSort::UpdatedAt, Order::Desc)
+///         .title("example")
+///         .per_page(50);
+///     let playbooks = client.playbooks().list(Some(options)).await.unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ListOptions<S> {
+    sort: Vec<(S, Order)>,
+    filters: Vec<(&'static str, String)>,
+    page: Option<u32>,
+    per_page: Option<u32>,
+    params: HashMap<String, String>,
+}
+
+impl<S> Default for ListOptions<S> {
+    fn default() -> Self {
+        Self {
+            sort: Vec::new(),
+            filters: Vec::new(),
+            page: None,
+            per_page: None,
+            params: HashMap::new(),
+        }
+    }
+}
+
+impl<S: SortField> ListOptions<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sorts by `field`, can be given several times to break ties with the next fields.
+    pub fn sort(mut self, field: S, order: Order) -> Self {
+        self.sort.push((field, order));
+        self
+    }
+
+    /// Sets the page to fetch, starting at 1.
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Sets the number of items per page.
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    /// Sets a raw query parameter, overriding the typed options with the same name.
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    fn filter(mut self, key: &'static str, value: &str) -> Self {
+        self.filters.retain(|(k, _)| *k != key);
+        self.filters.push((key, value.to_string()));
+        self
+    }
+
+    /// Returns the query string parameters of the options.
+    pub fn to_query(&self) -> HashMap<String, String> {
+        let mut query = HashMap::new();
+        if !self.sort.is_empty() {
+            let sort: Vec<String> = self
+                .sort
+                .iter()
+                .map(|(field, order)| format!("{}:{}", field.as_str(), order.as_str()))
+                .collect();
+            query.insert("sort".to_string(), sort.join(","));
+        }
+        for (key, value) in &self.filters {
+            query.insert(key.to_string(), value.clone());
+        }
+        if let Some(page) = self.page {
+            query.insert("page".to_string(), page.to_string());
+        }
+        if let Some(per_page) = self.per_page {
+            query.insert("per_page".to_string(), per_page.to_string());
+        }
+        query.extend(self.params.clone());
+        query
+    }
+}
+
+impl ListOptions<PlaybookSort> {
+    /// Only keeps the playbooks whose title contains `title`.
+    pub fn title(self, title: &str) -> Self {
+        self.filter("title", title)
+    }
+}
+
+impl ListOptions<ActorSort> {
+    /// Only keeps the actors whose name contains `name`.
+    pub fn name(self, name: &str) -> Self {
+        self.filter("name", name)
+    }
+}
+
+/// Uses a raw map of query parameters as the options.
+impl<S> From<HashMap<String, String>> for ListOptions<S> {
+    fn from(params: HashMap<String, String>) -> Self {
+        Self {
+            params,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{ListOptions, Order, PlaybookSort};
+
+    #[test]
+    fn serializes_to_the_query_string() {
+        let options = ListOptions::new()
+            .sort(PlaybookSort::UpdatedAt, Order::Desc)
+            .sort(PlaybookSort::Id, Order::Asc)
+            .title("old")
+            .title("example")
+            .page(2)
+            .per_page(50)
+            .param("state", "running");
+
+        let expected: HashMap<String, String> = [
+            ("sort", "updated_at:desc,id:asc"),
+            ("title", "example"),
+            ("page", "2"),
+            ("per_page", "50"),
+            ("state", "running"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert_eq!(expected, options.to_query());
+    }
+
+    #[test]
+    fn raw_params_override_typed_options() {
+        let raw = HashMap::from([("page".to_string(), "7".to_string())]);
+        let options = ListOptions::<PlaybookSort>::from(raw).page(2);
+
+        assert_eq!(Some(&"7".to_string()), options.to_query().get("page"));
+    }
+}
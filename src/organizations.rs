@@ -0,0 +1,230 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amp_common::http::{
+    endpoint::{Empty, Endpoint},
+    HTTPError,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, PercentEncode, CONTROLS};
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::timestamp::Timestamp;
+
+/// The role of an account within an organization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Manages the organization itself, including its billing and its owners.
+    Owner,
+    /// Manages the members and all the playbooks of the organization.
+    Admin,
+    /// Works with the playbooks of the organization.
+    Member,
+    /// A role this version of the client does not know about.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Organization {
+    /// The organization ID
+    pub id: u64,
+    /// The unique name of the organization, used to scope requests to it
+    pub name: String,
+    /// The display name of the organization
+    pub title: String,
+    /// The role of the current account within the organization
+    pub role: Role,
+    /// When the organization was created in Amphitheatre
+    pub created_at: Timestamp,
+    /// When the organization was updated in Amphitheatre
+    pub updated_at: Timestamp,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Member {
+    /// The account ID of the member
+    pub account_id: u64,
+    /// The account email of the member
+    pub email: String,
+    /// The account name of the member
+    pub name: String,
+    /// The role of the member within the organization
+    pub role: Role,
+    /// When the member joined the organization
+    pub joined_at: Timestamp,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MemberPayload {
+    /// The email of the account to add
+    pub email: String,
+    /// The role given to the account
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize)]
+struct RolePayload {
+    role: Role,
+}
+
+struct OrganizationEndpoint;
+
+impl Endpoint for OrganizationEndpoint {
+    type Output = Organization;
+}
+
+struct OrganizationsEndpoint;
+
+impl Endpoint for OrganizationsEndpoint {
+    type Output = Vec<Organization>;
+}
+
+struct MemberEndpoint;
+
+impl Endpoint for MemberEndpoint {
+    type Output = Member;
+}
+
+struct MembersEndpoint;
+
+impl Endpoint for MembersEndpoint {
+    type Output = Vec<Member>;
+}
+
+/// The Organizations Service handles the organizations endpoint of the Amphitheatre API.
+///
+/// Playbooks and actors can be managed under an organization instead of the
+/// personal account, either for a whole client with `Client::with_organization`,
+/// or for some calls with `Playbooks::in_organization` and `Actors::in_organization`.
+///
+/// See [API Documentation: organization](https://docs.amphitheatre.app/api/organization)
+pub struct Organizations<'a> {
+    pub client: &'a Client,
+}
+
+impl Organizations<'_> {
+    /// Lists the organizations the current account is a member of.
+    pub async fn list(&self) -> Result<Vec<Organization>, HTTPError> {
+        let res = self.client.get::<OrganizationsEndpoint>("/orgs", None).await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Retrieve an organization
+    ///
+    /// # Arguments
+    ///
+    /// `org`: The name of the organization
+    pub async fn get(&self, org: &str) -> Result<Organization, HTTPError> {
+        let path = format!("/orgs/{}", segment(org));
+        let res = self.client.get::<OrganizationEndpoint>(&path, None).await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Lists the members of an organization along with their roles.
+    ///
+    /// # Arguments
+    ///
+    /// `org`: The name of the organization
+    pub async fn members(&self, org: &str) -> Result<Vec<Member>, HTTPError> {
+        let path = format!("/orgs/{}/members", segment(org));
+        let res = self.client.get::<MembersEndpoint>(&path, None).await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Add an account to an organization.
+    ///
+    /// # Arguments
+    ///
+    /// `org`: The name of the organization
+    /// `payload`: The `MemberPayload` with the account to add and its role
+    pub async fn add_member(&self, org: &str, payload: MemberPayload) -> Result<Member, HTTPError> {
+        let path = format!("/orgs/{}/members", segment(org));
+        let res = self
+            .client
+            .post::<MemberEndpoint, MemberPayload>(&path, &payload)
+            .await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Change the role of a member of an organization.
+    ///
+    /// # Arguments
+    ///
+    /// `org`: The name of the organization
+    /// `account_id`: The account ID of the member
+    /// `role`: The new role of the member
+    pub async fn update_member(&self, org: &str, account_id: u64, role: Role) -> Result<Member, HTTPError> {
+        let path = format!("/orgs/{}/members/{account_id}", segment(org));
+        let res = self
+            .client
+            .patch::<MemberEndpoint, RolePayload>(&path, &RolePayload { role })
+            .await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Remove a member from an organization.
+    ///
+    /// # Arguments
+    ///
+    /// `org`: The name of the organization
+    /// `account_id`: The account ID of the member
+    pub async fn remove_member(&self, org: &str, account_id: u64) -> Result<u16, HTTPError> {
+        let path = format!("/orgs/{}/members/{account_id}", segment(org));
+        Ok(self.client.delete::<Empty>(&path).await?.status)
+    }
+}
+
+/// Returns `path` under the given organization, or unchanged for the personal account.
+pub(crate) fn scoped(org: Option<&str>, path: &str) -> String {
+    match org {
+        Some(org) => format!("/orgs/{}{path}", segment(org)),
+        None => path.to_string(),
+    }
+}
+
+/// The characters escaped in a path segment, as listed by the URL standard, plus `/` and `%`.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Escapes the name of an organization to be used as a single path segment.
+fn segment(org: &str) -> PercentEncode<'_> {
+    utf8_percent_encode(org, SEGMENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scoped;
+
+    #[test]
+    fn scopes_paths_to_an_encoded_organization() {
+        assert_eq!("/playbooks", scoped(None, "/playbooks"));
+        assert_eq!("/orgs/acme/playbooks", scoped(Some("acme"), "/playbooks"));
+        assert_eq!(
+            "/orgs/acme%2Fcorp%20%3Fx/playbooks",
+            scoped(Some("acme/corp ?x"), "/playbooks")
+        );
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use futures::{stream, Stream, TryStreamExt};
+use reqwest::header::{HeaderMap, LINK};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use serde_json::value::RawValue;
+
+use crate::client::Client;
+use crate::errors::Error;
+
+/// The page size used when the caller did not ask for one.
+const DEFAULT_PER_PAGE: u32 = 30;
+
+/// The pagination details a list endpoint may send along with a page.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub(crate) struct Pagination {
+    pub current_page: u32,
+    pub total_pages: u32,
+}
+
+/// A page of a list endpoint, sent either as a bare array
+/// or as a `{"data": [...], "pagination": {...}}` envelope.
+#[derive(Debug)]
+pub(crate) struct Page<T> {
+    pub items: Vec<T>,
+    pub pagination: Option<Pagination>,
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Page<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw<T> {
+            Items(Vec<T>),
+            Envelope {
+                data: Vec<T>,
+                pagination: Option<Pagination>,
+            },
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Items(items) => Page {
+                items,
+                pagination: None,
+            },
+            Raw::Envelope { data, pagination } => Page {
+                items: data,
+                pagination,
+            },
+        })
+    }
+}
+
+/// Sets `per_page` in `query` unless the caller already did, returning the page size.
+fn per_page(query: &mut HashMap<String, String>) -> u32 {
+    let per_page = query
+        .get("per_page")
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .max(1);
+    query.insert("per_page".to_string(), per_page.to_string());
+    per_page
+}
+
+/// Where `paginate` is in the listing.
+struct Cursor {
+    page: u32,
+    /// The raw items of the previous page.
+    previous: Vec<String>,
+}
+
+/// Streams the items of the list endpoint at `path`, fetching the pages lazily.
+///
+/// Pages are requested with `page` starting at 1, and `per_page` from `query`
+/// or `DEFAULT_PER_PAGE`. Whether another page follows is told, in order, by a
+/// `Link` header with a `next` relation, the pagination envelope, the `X-Total-Pages`
+/// or `X-Total` headers, and without any of them, by a page holding `per_page` items.
+///
+/// The stream also ends on an empty page, or on a page holding the same items
+/// as the previous one, as sent by a server ignoring the `page` parameter.
+pub(crate) fn paginate<'a, T>(
+    client: &'a Client,
+    path: String,
+    mut query: HashMap<String, String>,
+) -> impl Stream<Item = Result<T, Error>> + 'a
+where
+    T: DeserializeOwned + 'a,
+{
+    let per_page = per_page(&mut query);
+    let start = Cursor {
+        page: 1,
+        previous: Vec::new(),
+    };
+
+    stream::try_unfold(Some(start), move |cursor| {
+        let path = path.clone();
+        let mut query = query.clone();
+        async move {
+            let Some(cursor) = cursor else {
+                return Ok(None);
+            };
+
+            query.insert("page".to_string(), cursor.page.to_string());
+            let res = client
+                .raw(Method::GET, &path, |request| request.query(&query))
+                .await?;
+            if !res.status().is_success() {
+                return Err(Error::from_response(res).await);
+            }
+
+            let headers = res.headers().clone();
+            let page: Page<Box<RawValue>> = serde_json::from_slice(&res.bytes().await?)?;
+            let raw: Vec<String> = page.items.iter().map(|item| item.get().to_string()).collect();
+            if raw.is_empty() || (cursor.page > 1 && raw == cursor.previous) {
+                return Ok(None);
+            }
+
+            let items = raw
+                .iter()
+                .map(|item| serde_json::from_str(item))
+                .collect::<Result<Vec<T>, _>>()?;
+            let more = link_has_next(&headers)
+                .or(page.pagination.map(|p| p.current_page < p.total_pages))
+                .or_else(|| totals_have_more(&headers, cursor.page, per_page))
+                .unwrap_or(items.len() >= per_page as usize);
+            let next = more.then(|| Cursor {
+                page: cursor.page + 1,
+                previous: raw,
+            });
+            Ok(Some((stream::iter(items.into_iter().map(Ok)), next)))
+        }
+    })
+    .try_flatten()
+}
+
+/// Tells whether the `Link` header (RFC 8288) has a `next` relation, if the server sent one.
+fn link_has_next(headers: &HeaderMap) -> Option<bool> {
+    let mut links = headers.get_all(LINK).iter().peekable();
+    links.peek()?;
+
+    Some(links.filter_map(|value| value.to_str().ok()).any(|value| {
+        value.split(',').any(|link| {
+            link.split(';').skip(1).any(|param| {
+                let Some((name, rel)) = param.split_once('=') else {
+                    return false;
+                };
+                name.trim().eq_ignore_ascii_case("rel")
+                    && rel
+                        .trim()
+                        .trim_matches('"')
+                        .split_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("next"))
+            })
+        })
+    }))
+}
+
+/// Tells whether pages follow `page` according to the `X-Total-Pages` header,
+/// or the `X-Total` count of items, if the server sent one of them.
+fn totals_have_more(headers: &HeaderMap, page: u32, per_page: u32) -> Option<bool> {
+    let number = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+
+    let page = number("x-page").unwrap_or(u64::from(page));
+    if let Some(total_pages) = number("x-total-pages") {
+        return Some(page < total_pages);
+    }
+    number("x-total").map(|total| page * u64::from(per_page) < total)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    use super::{link_has_next, totals_have_more};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn reads_the_next_relation_of_link_headers() {
+        let next = headers(&[(
+            "link",
+            r#"<https://cloud.amphitheatre.app/v1/playbooks?page=3>; rel="next", <https://cloud.amphitheatre.app/v1/playbooks?page=9>; rel="last""#,
+        )]);
+        assert_eq!(Some(true), link_has_next(&next));
+
+        let last = headers(&[(
+            "link",
+            r#"<https://cloud.amphitheatre.app/v1/playbooks?page=1>; rel="first prev""#,
+        )]);
+        assert_eq!(Some(false), link_has_next(&last));
+        assert_eq!(None, link_has_next(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn reads_the_totals_headers() {
+        assert_eq!(
+            Some(true),
+            totals_have_more(&headers(&[("x-total-pages", "3")]), 2, 30)
+        );
+        assert_eq!(
+            Some(false),
+            totals_have_more(&headers(&[("x-total-pages", "3")]), 3, 30)
+        );
+        assert_eq!(
+            Some(true),
+            totals_have_more(&headers(&[("x-total", "61")]), 2, 30)
+        );
+        assert_eq!(
+            Some(false),
+            totals_have_more(&headers(&[("x-total", "60")]), 2, 30)
+        );
+        assert_eq!(None, totals_have_more(&HeaderMap::new(), 1, 30));
+    }
+}
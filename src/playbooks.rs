@@ -12,18 +12,55 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
 
 use amp_common::{
     http::{
         endpoint::{Empty, Endpoint},
-        Client, HTTPError,
+        HTTPError,
     },
     resource::{PlaybookSpec, Preface},
 };
+use futures::{Stream, TryStreamExt};
+use reqwest::header::{ETAG, IF_MATCH};
+use reqwest::{Method, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use crate::client::Client;
+use crate::document::{Format, PlaybookDocument};
+use crate::errors::Error;
+use crate::options::{ListOptions, PlaybookSort};
+use crate::organizations::scoped;
+use crate::pagination::{paginate, Page};
+use crate::repository::RepositoryPlaybook;
+use crate::timestamp::Timestamp;
+
+/// A playbook as returned by the server.
+///
+/// The fields of the `PlaybookSpec` can be read directly on it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Playbook {
+    #[serde(flatten)]
+    pub spec: PlaybookSpec,
+    /// When the playbook was created in Amphitheatre
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<Timestamp>,
+    /// When the playbook was updated in Amphitheatre
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<Timestamp>,
+}
+
+impl Deref for Playbook {
+    type Target = PlaybookSpec;
+
+    fn deref(&self) -> &Self::Target {
+        &self.spec
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PlaybookPayload {
     /// The title of the playbook
@@ -34,16 +71,120 @@ pub struct PlaybookPayload {
     pub preface: Preface,
 }
 
+/// The name of the character manifest in a source directory.
+pub const MANIFEST_FILE: &str = ".amp.toml";
+
+impl PlaybookPayload {
+    /// Builds the payload of a playbook led by the character described in a manifest.
+    ///
+    /// `path` is either the manifest itself or a directory holding a `.amp.toml`
+    /// manifest. The title and description of the playbook are taken from the
+    /// metadata of the character.
+    pub fn from_manifest<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut path = path.as_ref().to_path_buf();
+        if path.is_dir() {
+            path.push(MANIFEST_FILE);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let preface = Preface {
+            manifest: Some(toml::from_str(&content)?),
+            ..Default::default()
+        };
+
+        let meta = &preface.manifest.as_ref().unwrap().meta;
+        if meta.name.trim().is_empty() {
+            return Err(Error::Validation(format!(
+                "{}: the character has no name",
+                path.display()
+            )));
+        }
+        if meta.version.trim().is_empty() {
+            return Err(Error::Validation(format!(
+                "{}: the character has no version",
+                path.display()
+            )));
+        }
+
+        let title = meta.name.clone();
+        let description = meta.description.clone().unwrap_or_default();
+        Ok(Self {
+            title: title.clone(),
+            description,
+            preface: Preface {
+                name: title,
+                ..preface
+            },
+        })
+    }
+}
+
+/// Represents the changes to a playbook, fields left to `None` are not changed.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct PlaybookUpdate {
+    /// The new title of the playbook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The new description of the playbook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The new leading character manifest of the playbook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preface: Option<Preface>,
+}
+
+/// Updates every field of the playbook.
+impl From<PlaybookPayload> for PlaybookUpdate {
+    fn from(payload: PlaybookPayload) -> Self {
+        Self {
+            title: Some(payload.title),
+            description: Some(payload.description),
+            preface: Some(payload.preface),
+        }
+    }
+}
+
+/// A resource along with the ETag identifying its version on the server.
+#[derive(Debug)]
+pub struct Versioned<T> {
+    /// The ETag of the version, `None` if the server did not send one
+    pub etag: Option<String>,
+    /// The resource itself
+    pub value: T,
+}
+
+/// The outcome of `Playbooks::apply`, holding the playbook as it is on the server.
+#[derive(Debug)]
+pub enum Applied {
+    /// No playbook had the title, it was created.
+    Created(Playbook),
+    /// The playbook differed from the payload, the changed fields were updated.
+    Updated(Playbook),
+    /// The playbook already matched the payload, nothing was sent.
+    Unchanged(Playbook),
+}
+
+impl Applied {
+    /// Returns the playbook, whatever the outcome.
+    pub fn playbook(&self) -> &Playbook {
+        match self {
+            Applied::Created(playbook) | Applied::Updated(playbook) | Applied::Unchanged(playbook) => {
+                playbook
+            }
+        }
+    }
+}
+
 struct PlaybookEndpoint;
 
 impl Endpoint for PlaybookEndpoint {
-    type Output = PlaybookSpec;
+    type Output = Playbook;
 }
 
 struct PlaybooksEndpoint;
 
 impl Endpoint for PlaybooksEndpoint {
-    type Output = Vec<PlaybookSpec>;
+    type Output = Page<Playbook>;
 }
 
 /// The Playbooks Service handles the playbooks endpoint of the Amphitheatre API.
@@ -51,24 +192,85 @@ impl Endpoint for PlaybooksEndpoint {
 /// See [API Documentation: playbook](https://docs.amphitheatre.app/api/playbook)
 pub struct Playbooks<'a> {
     pub client: &'a Client,
+    /// The organization the playbooks belong to, `None` for the personal account
+    pub organization: Option<String>,
 }
 
-impl Playbooks<'_> {
+impl<'a> Playbooks<'a> {
+    /// Manages the playbooks of the given organization instead of the personal account.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let playbooks = client.playbooks().in_organization("acme").list(None).await.unwrap();
+    /// }
+    /// ```
+    pub fn in_organization(mut self, org: &str) -> Self {
+        self.organization = Some(org.to_string());
+        self
+    }
+
     /// Lists the playbooks in the current account.
     ///
     /// # Arguments
     ///
-    /// `options`: The `RequestOptions`
-    ///             - Sort: `id`, `label`, `email`
-    pub async fn list(
-        &self,
-        options: Option<HashMap<String, String>>,
-    ) -> Result<Vec<PlaybookSpec>, HTTPError> {
+    /// `options`: The `ListOptions` to sort, filter and paginate the playbooks
+    pub async fn list(&self, options: Option<ListOptions<PlaybookSort>>) -> Result<Vec<Playbook>, HTTPError> {
+        let options = options.map(|o| o.to_query());
         let res = self
             .client
-            .get::<PlaybooksEndpoint>("/playbooks", options)
+            .get::<PlaybooksEndpoint>(&self.path("/playbooks"), options)
             .await?;
-        Ok(res.data.unwrap())
+        Ok(res.data.unwrap().items)
+    }
+
+    /// Streams all the playbooks in the current account, fetching the pages
+    /// lazily as the stream is consumed.
+    ///
+    /// The `page` of the options is ignored, every page is fetched in turn.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use futures::TryStreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///
+    ///     let mut playbooks = Box::pin(client.playbooks().list_stream(None));
+    ///     while let Some(playbook) = playbooks.try_next().await.unwrap() {
+    ///         println!("{}", playbook.title);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `options`: The `ListOptions` to sort, filter and size the pages
+    pub fn list_stream(
+        &self,
+        options: Option<ListOptions<PlaybookSort>>,
+    ) -> impl Stream<Item = Result<Playbook, Error>> + 'a {
+        let query = options.map(|o| o.to_query()).unwrap_or_default();
+        paginate(self.client, self.path("/playbooks"), query)
+    }
+
+    /// Lists all the playbooks in the current account, following every page.
+    ///
+    /// # Arguments
+    ///
+    /// `options`: The `ListOptions` to sort, filter and size the pages
+    pub async fn list_all(&self, options: Option<ListOptions<PlaybookSort>>) -> Result<Vec<Playbook>, Error> {
+        self.list_stream(options).try_collect().await
     }
 
     /// Create a playbook in the account.
@@ -77,48 +279,276 @@ impl Playbooks<'_> {
     ///
     /// `payload`: the `PlaybookPayload` with the information needed to create
     /// the playbook
-    pub async fn create(&self, payload: PlaybookPayload) -> Result<PlaybookSpec, HTTPError> {
+    pub async fn create(&self, payload: PlaybookPayload) -> Result<Playbook, HTTPError> {
         let res = self
             .client
-            .post::<PlaybookEndpoint, PlaybookPayload>("/playbooks", &payload)
+            .post::<PlaybookEndpoint, PlaybookPayload>(&self.path("/playbooks"), &payload)
             .await?;
         Ok(res.data.unwrap())
     }
 
+    /// Create a playbook led by the character described in a local manifest.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let playbook = client.playbooks().create_from_manifest(".").await.unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `path`: The manifest, or a directory holding a `.amp.toml` manifest
+    pub async fn create_from_manifest<P: AsRef<Path>>(&self, path: P) -> Result<Playbook, Error> {
+        let payload = PlaybookPayload::from_manifest(path)?;
+        Ok(self.create(payload).await?)
+    }
+
+    /// Starts building a playbook led by the character found in a Git repository.
+    ///
+    /// See `RepositoryPlaybook` for the options.
+    ///
+    /// # Arguments
+    ///
+    /// `repo`: The URL of the repository
+    pub fn repository(&self, repo: &str) -> RepositoryPlaybook<'a> {
+        let playbooks = Playbooks {
+            client: self.client,
+            organization: self.organization.clone(),
+        };
+        RepositoryPlaybook::new(playbooks, repo)
+    }
+
     /// Retrieve a playbook
     ///
     /// # Arguments
     ///
     /// `pid`: The ID of the playbook we want to retrieve
-    pub async fn get(&self, pid: &str) -> Result<PlaybookSpec, HTTPError> {
-        let path = format!("/playbooks/{pid}");
+    pub async fn get(&self, pid: &str) -> Result<Playbook, HTTPError> {
+        let path = self.path(&format!("/playbooks/{pid}"));
         let res = self.client.get::<PlaybookEndpoint>(&path, None).await?;
         Ok(res.data.unwrap())
     }
 
-    /// Update a playbook
+    /// Retrieve a playbook along with the ETag of its current version,
+    /// to be passed to `update_if_match`.
+    ///
+    /// # Arguments
+    ///
+    /// `pid`: The ID of the playbook we want to retrieve
+    pub async fn get_versioned(&self, pid: &str) -> Result<Versioned<Playbook>, Error> {
+        let path = self.path(&format!("/playbooks/{pid}"));
+        let res = self.client.raw(Method::GET, &path, |request| request).await?;
+        if !res.status().is_success() {
+            return Err(Error::from_response(res).await);
+        }
+
+        versioned(res).await
+    }
+
+    /// Update a playbook, only the fields set in the payload are changed.
     ///
     /// # Arguments
     ///
     /// `pid`: The playbook id
-    /// `payload`: The `PlaybookPayload` with the information needed to update
-    pub async fn update(&self, pid: &str, payload: PlaybookPayload) -> Result<PlaybookSpec, HTTPError> {
-        let path = format!("/playbooks/{pid}");
+    /// `payload`: The `PlaybookUpdate`, or a full `PlaybookPayload`, with the changes
+    pub async fn update<P: Into<PlaybookUpdate>>(
+        &self,
+        pid: &str,
+        payload: P,
+    ) -> Result<Playbook, HTTPError> {
+        let path = self.path(&format!("/playbooks/{pid}"));
         let res = self
             .client
-            .patch::<PlaybookEndpoint, PlaybookPayload>(&path, &payload)
+            .patch::<PlaybookEndpoint, PlaybookUpdate>(&path, &payload.into())
             .await?;
         Ok(res.data.unwrap())
     }
 
+    /// Update a playbook unless it was changed since the version identified by `etag`.
+    ///
+    /// Fails with `Error::Conflict`, carrying the ETag of the current version if the
+    /// server sent it, when someone else updated the playbook in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::errors::Error;
+    /// use amp_client::playbooks::PlaybookUpdate;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let playbooks = client.playbooks();
+    ///
+    ///     let current = playbooks.get_versioned("1").await.unwrap();
+    ///     let payload = PlaybookUpdate {
+    ///         title: Some(format!("{} (copy)", current.value.title)),
+    ///         ..Default::default()
+    ///     };
+    ///
+    ///     match playbooks.update_if_match("1", current.etag.as_deref().unwrap(), payload).await {
+    ///         Ok(updated) => println!("updated to {:?}", updated.etag),
+    ///         Err(Error::Conflict { .. }) => println!("changed by someone else, try again"),
+    ///         Err(err) => panic!("{err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `pid`: The playbook id
+    /// `etag`: The ETag of the version the changes are based on
+    /// `payload`: The `PlaybookUpdate` with the changes
+    pub async fn update_if_match<P: Into<PlaybookUpdate>>(
+        &self,
+        pid: &str,
+        etag: &str,
+        payload: P,
+    ) -> Result<Versioned<Playbook>, Error> {
+        let path = self.path(&format!("/playbooks/{pid}"));
+        let payload = payload.into();
+        let res = self
+            .client
+            .raw(Method::PATCH, &path, |request| {
+                request.header(IF_MATCH, etag).json(&payload)
+            })
+            .await?;
+
+        match res.status() {
+            StatusCode::PRECONDITION_FAILED | StatusCode::CONFLICT => {
+                Err(Error::Conflict { etag: etag_of(&res) })
+            }
+            status if status.is_success() => versioned(res).await,
+            _ => Err(Error::from_response(res).await),
+        }
+    }
+
+    /// Makes sure a playbook matching the payload exists, using its title as the key.
+    ///
+    /// The playbook with the same title is updated with the fields that differ
+    /// from the payload, or created if there is none. Fails with `Error::Validation`
+    /// if several playbooks share the title.
+    ///
+    /// The changes are computed against the current version of the playbook, and
+    /// sent with its ETag: if the playbook is changed in the meantime, this fails
+    /// with `Error::Conflict` instead of overwriting the other changes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::playbooks::{Applied, PlaybookPayload};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let payload = PlaybookPayload::from_manifest(".").unwrap();
+    ///
+    ///     match client.playbooks().apply(payload).await.unwrap() {
+    ///         Applied::Created(playbook) => println!("created {}", playbook.id),
+    ///         Applied::Updated(playbook) => println!("updated {}", playbook.id),
+    ///         Applied::Unchanged(playbook) => println!("{} is up to date", playbook.id),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `payload`: The `PlaybookPayload` describing the desired playbook
+    pub async fn apply(&self, payload: PlaybookPayload) -> Result<Applied, Error> {
+        let options = ListOptions::new().title(&payload.title);
+        let mut existing: Vec<Playbook> = self
+            .list_stream(Some(options))
+            .try_filter(|playbook| futures::future::ready(playbook.title == payload.title))
+            .try_collect()
+            .await?;
+
+        let listed = match existing.len() {
+            0 => return Ok(Applied::Created(self.create(payload).await?)),
+            1 => existing.remove(0),
+            n => {
+                return Err(Error::Validation(format!(
+                    "{n} playbooks are titled {:?}, can't tell which one to apply to",
+                    payload.title
+                )))
+            }
+        };
+
+        let Versioned { etag, value: current } = self.get_versioned(&listed.id).await?;
+        let mut changes = PlaybookUpdate::default();
+        if current.description.as_deref().unwrap_or_default() != payload.description {
+            changes.description = Some(payload.description);
+        }
+        if serde_json::to_value(&current.preface)? != serde_json::to_value(&payload.preface)? {
+            changes.preface = Some(payload.preface);
+        }
+
+        if changes.description.is_none() && changes.preface.is_none() {
+            return Ok(Applied::Unchanged(current));
+        }
+
+        let updated = match etag {
+            Some(etag) => self.update_if_match(&current.id, &etag, changes).await?.value,
+            None => self.update(&current.id, changes).await?,
+        };
+        Ok(Applied::Updated(updated))
+    }
+
+    /// Export a playbook as a portable document, without the fields assigned by the server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::document::Format;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let yaml = client.playbooks().export("1", Format::Yaml).await.unwrap();
+    ///     std::fs::write("playbook.yaml", yaml).unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `pid`: The playbook id
+    /// `format`: The `Format` of the document
+    pub async fn export(&self, pid: &str, format: Format) -> Result<String, Error> {
+        let playbook = self.get(pid).await?;
+        PlaybookDocument::from_spec(&playbook)?.to_string(format)
+    }
+
+    /// Create a playbook from a document written by `export`, possibly in another environment.
+    ///
+    /// # Arguments
+    ///
+    /// `document`: The content of the document
+    /// `format`: The `Format` of the document
+    pub async fn import(&self, document: &str, format: Format) -> Result<Playbook, Error> {
+        let payload = PlaybookDocument::parse(document, format)?.into_payload();
+        Ok(self.create(payload).await?)
+    }
+
     /// Delete a playbook
     ///
     /// # Arguments
     ///
     /// `pid`: The playbook id
     pub async fn delete(&self, pid: &str) -> Result<u16, HTTPError> {
-        let path = format!("/playbooks/{pid}");
-        Ok(self.client.delete::<Empty>(&path).await?.status.as_u16())
+        let path = self.path(&format!("/playbooks/{pid}"));
+        Ok(self.client.delete::<Empty>(&path).await?.status)
     }
 
     /// Retrieve the event streams of playbook
@@ -137,13 +567,12 @@ impl Playbooks<'_> {
     ///
     /// `pid`: The playbook id
     pub async fn start(&self, pid: &str) -> Result<u16, HTTPError> {
-        let path = format!("/playbooks/{pid}/actions/start");
+        let path = self.path(&format!("/playbooks/{pid}/actions/start"));
         Ok(self
             .client
             .post::<Empty, Value>(&path, &json!(null))
             .await?
-            .status
-            .as_u16())
+            .status)
     }
 
     /// Stop a playbook
@@ -152,12 +581,28 @@ impl Playbooks<'_> {
     ///
     /// `pid`: The playbook id
     pub async fn stop(&self, pid: &str) -> Result<u16, HTTPError> {
-        let path = format!("/playbooks/{pid}/actions/stop");
+        let path = self.path(&format!("/playbooks/{pid}/actions/stop"));
         Ok(self
             .client
             .post::<Empty, Value>(&path, &json!(null))
             .await?
-            .status
-            .as_u16())
+            .status)
+    }
+
+    fn path(&self, path: &str) -> String {
+        scoped(self.organization.as_deref(), path)
     }
 }
+
+fn etag_of(res: &reqwest::Response) -> Option<String> {
+    res.headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string)
+}
+
+async fn versioned(res: reqwest::Response) -> Result<Versioned<Playbook>, Error> {
+    let etag = etag_of(&res);
+    let value = serde_json::from_slice(&res.bytes().await?)?;
+    Ok(Versioned { etag, value })
+}
@@ -0,0 +1,278 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::{Component, Path};
+
+use amp_common::resource::Preface;
+use amp_common::schema::GitReference;
+use url::Url;
+
+use crate::errors::Error;
+use crate::playbooks::{Playbook, PlaybookPayload, Playbooks};
+
+/// The revision of a repository a playbook is built from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Reference {
+    Branch(String),
+    Tag(String),
+    Commit(String),
+}
+
+/// Builds a playbook led by the character found in a Git repository,
+/// created with `Playbooks::repository`.
+///
+/// Without a branch, tag or commit, the default branch of the repository is used.
+/// Setting one of them replaces the previous one.
+///
+/// # Examples
+///
+/// ```no_run
+/// use amp_client::client::Client;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let token = Some(String::from("AUTH_TOKEN"));
+///     let client = Client::new("https://cloud.amphitheatre.app", token);
+///
+///     let playbook = client
+///         .playbooks()
+///         .repository("https://github.com/amphitheatre-app/amp-example-go")
+///         .branch("feature/preview")
+///         .path("services/api")
+///         .create()
+///         .await
+///         .unwrap();
+/// }
+/// ```
+pub struct RepositoryPlaybook<'a> {
+    playbooks: Playbooks<'a>,
+    repo: String,
+    reference: Option<Reference>,
+    path: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+}
+
+impl<'a> RepositoryPlaybook<'a> {
+    pub(crate) fn new(playbooks: Playbooks<'a>, repo: &str) -> Self {
+        Self {
+            playbooks,
+            repo: repo.trim().to_string(),
+            reference: None,
+            path: None,
+            title: None,
+            description: None,
+        }
+    }
+
+    /// Builds the playbook from the head of `branch`.
+    pub fn branch(mut self, branch: &str) -> Self {
+        self.reference = Some(Reference::Branch(branch.to_string()));
+        self
+    }
+
+    /// Builds the playbook from `tag`.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.reference = Some(Reference::Tag(tag.to_string()));
+        self
+    }
+
+    /// Builds the playbook from `commit`, a full or abbreviated commit hash.
+    pub fn commit(mut self, commit: &str) -> Self {
+        self.reference = Some(Reference::Commit(commit.to_string()));
+        self
+    }
+
+    /// Looks for the character in `path`, relative to the root of the repository.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Sets the title of the playbook, the name of the repository by default.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Sets the description of the playbook.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Validates the repository reference and builds the payload of the playbook,
+    /// without sending it.
+    pub fn build(&self) -> Result<PlaybookPayload, Error> {
+        let name = repository_name(&self.repo)?;
+
+        let (mut branch, mut tag, mut rev) = (None, None, None);
+        match &self.reference {
+            Some(Reference::Branch(b)) => branch = Some(non_empty("branch", b)?),
+            Some(Reference::Tag(t)) => tag = Some(non_empty("tag", t)?),
+            Some(Reference::Commit(c)) => rev = Some(commit_hash(c)?),
+            None => {}
+        }
+        let path = self.path.as_deref().map(subpath).transpose()?.flatten();
+
+        let title = self.title.clone().unwrap_or_else(|| name.clone());
+        Ok(PlaybookPayload {
+            title,
+            description: self.description.clone().unwrap_or_default(),
+            preface: Preface {
+                name,
+                repository: Some(GitReference {
+                    repo: self.repo.clone(),
+                    branch,
+                    tag,
+                    rev,
+                    path,
+                }),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Validates the repository reference and creates the playbook.
+    pub async fn create(self) -> Result<Playbook, Error> {
+        let payload = self.build()?;
+        Ok(self.playbooks.create(payload).await?)
+    }
+}
+
+/// Returns the name of the repository at `repo`, failing if it isn't a valid Git URL.
+///
+/// Besides `https`, `http`, `ssh` and `git` URLs, the scp-like `user@host:owner/repo`
+/// syntax is accepted.
+fn repository_name(repo: &str) -> Result<String, Error> {
+    let invalid = || Error::Validation(format!("{repo:?} is not a valid Git repository URL"));
+
+    let path = match Url::parse(repo) {
+        Ok(url) => {
+            if !matches!(url.scheme(), "https" | "http" | "ssh" | "git") || url.host_str().is_none() {
+                return Err(invalid());
+            }
+            url.path().to_string()
+        }
+        Err(_) => {
+            // scp-like syntax, e.g. git@github.com:owner/repo.git
+            let (host, path) = repo.split_once(':').ok_or_else(invalid)?;
+            let host = host.rsplit('@').next().unwrap_or_default();
+            if host.is_empty() || host.contains('/') || path.starts_with('/') {
+                return Err(invalid());
+            }
+            path.to_string()
+        }
+    };
+
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if name.is_empty() || name.chars().any(char::is_whitespace) {
+        return Err(invalid());
+    }
+    Ok(name.to_string())
+}
+
+fn non_empty(kind: &str, value: &str) -> Result<String, Error> {
+    let value = value.trim();
+    if value.is_empty() || value.chars().any(char::is_whitespace) {
+        return Err(Error::Validation(format!("{value:?} is not a valid {kind}")));
+    }
+    Ok(value.to_string())
+}
+
+fn commit_hash(commit: &str) -> Result<String, Error> {
+    let commit = commit.trim();
+    if !(7..=40).contains(&commit.len()) || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::Validation(format!(
+            "{commit:?} is not a valid commit hash"
+        )));
+    }
+    Ok(commit.to_lowercase())
+}
+
+/// Normalizes a path inside the repository, `None` for its root.
+fn subpath(path: &str) -> Result<Option<String>, Error> {
+    let mut parts = Vec::new();
+    for component in Path::new(path.trim()).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            _ => {
+                return Err(Error::Validation(format!(
+                    "{path:?} is not a relative path inside the repository"
+                )))
+            }
+        }
+    }
+    Ok((!parts.is_empty()).then(|| parts.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commit_hash, repository_name, subpath};
+
+    #[test]
+    fn accepts_git_urls() {
+        for (repo, name) in [
+            (
+                "https://github.com/amphitheatre-app/amp-example-go",
+                "amp-example-go",
+            ),
+            (
+                "https://github.com/amphitheatre-app/amp-example-go.git/",
+                "amp-example-go",
+            ),
+            (
+                "ssh://git@github.com/amphitheatre-app/amp-example-go.git",
+                "amp-example-go",
+            ),
+            (
+                "git@github.com:amphitheatre-app/amp-example-go.git",
+                "amp-example-go",
+            ),
+        ] {
+            assert_eq!(name, repository_name(repo).unwrap(), "{repo}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        for repo in [
+            "",
+            "amp-example-go",
+            "file:///tmp/amp-example-go",
+            "https://github.com/",
+            "git@github.com:/amp-example-go",
+            "/tmp/amp-example-go",
+        ] {
+            assert!(repository_name(repo).is_err(), "{repo}");
+        }
+    }
+
+    #[test]
+    fn validates_commits_and_paths() {
+        assert_eq!("0a1b2c3d", commit_hash("0A1B2C3D").unwrap());
+        assert!(commit_hash("main").is_err());
+        assert!(commit_hash("0a1b2c").is_err());
+
+        assert_eq!(
+            Some("services/api".to_string()),
+            subpath("./services//api/").unwrap()
+        );
+        assert_eq!(None, subpath(".").unwrap());
+        assert!(subpath("../secrets").is_err());
+        assert!(subpath("/etc").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A permission an access token can be granted.
+///
+/// Scopes compare by their string form, so `Scope::Other("playbooks:write")`
+/// is the same scope as `Scope::PlaybooksWrite`.
+#[derive(Clone, Debug)]
+pub enum Scope {
+    /// Read the account profile.
+    AccountRead,
+    /// Update the account profile.
+    AccountWrite,
+    /// List and read playbooks.
+    PlaybooksRead,
+    /// Create, update, start, stop and delete playbooks.
+    PlaybooksWrite,
+    /// List and read actors, including their logs, info and stats.
+    ActorsRead,
+    /// Sync source code into actors.
+    ActorsWrite,
+    /// A scope this version of the client does not know about.
+    Other(String),
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Scope::AccountRead => "account:read",
+            Scope::AccountWrite => "account:write",
+            Scope::PlaybooksRead => "playbooks:read",
+            Scope::PlaybooksWrite => "playbooks:write",
+            Scope::ActorsRead => "actors:read",
+            Scope::ActorsWrite => "actors:write",
+            Scope::Other(scope) => scope.as_str(),
+        }
+    }
+}
+
+impl PartialEq for Scope {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Scope {}
+
+impl Hash for Scope {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl FromStr for Scope {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "account:read" => Scope::AccountRead,
+            "account:write" => Scope::AccountWrite,
+            "playbooks:read" => Scope::PlaybooksRead,
+            "playbooks:write" => Scope::PlaybooksWrite,
+            "actors:read" => Scope::ActorsRead,
+            "actors:write" => Scope::ActorsWrite,
+            other => Scope::Other(other.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A set of scopes, exchanged with the server as a space-separated string.
+///
+/// # Examples
+///
+/// ```
+/// use amp_client::scopes::{Scope, Scopes};
+///
+/// let scopes: Scopes = "playbooks:read playbooks:write".parse().unwrap();
+///
+/// assert!(scopes.has(Scope::PlaybooksWrite));
+/// assert!(!scopes.has(Scope::ActorsWrite));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scopes(Vec<Scope>);
+
+impl Scopes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a scope, ignoring duplicates.
+    pub fn insert(&mut self, scope: Scope) {
+        if !self.0.contains(&scope) {
+            self.0.push(scope);
+        }
+    }
+
+    /// Returns `true` if `scope` is granted.
+    pub fn has(&self, scope: Scope) -> bool {
+        self.0.contains(&scope)
+    }
+
+    /// Returns `true` if every one of `scopes` is granted.
+    pub fn has_all(&self, scopes: &[Scope]) -> bool {
+        scopes.iter().all(|scope| self.0.contains(scope))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Scope> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Scope> for Scopes {
+    fn from_iter<I: IntoIterator<Item = Scope>>(iter: I) -> Self {
+        let mut scopes = Scopes::new();
+        for scope in iter {
+            scopes.insert(scope);
+        }
+        scopes
+    }
+}
+
+impl From<Scope> for Scopes {
+    fn from(scope: Scope) -> Self {
+        Scopes(vec![scope])
+    }
+}
+
+impl FromStr for Scopes {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.split_whitespace().map(|scope| scope.parse().unwrap()).collect())
+    }
+}
+
+impl fmt::Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, scope) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(scope.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Scopes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scopes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(s.parse().unwrap())
+    }
+}
+
+/// Whether a payload has no scope to send, in which case the `scope` parameter
+/// is left out rather than sent empty.
+pub(crate) fn is_unset(scopes: &Option<Scopes>) -> bool {
+    scopes.as_ref().is_none_or(Scopes::is_empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scope, Scopes};
+
+    #[test]
+    fn parses_and_formats_scopes() {
+        let scopes: Scopes = "playbooks:write  actors:read custom:thing playbooks:write"
+            .parse()
+            .unwrap();
+
+        assert!(scopes.has(Scope::PlaybooksWrite));
+        assert!(scopes.has(Scope::Other("custom:thing".to_string())));
+        assert!(scopes.has_all(&[Scope::PlaybooksWrite, Scope::ActorsRead]));
+        assert!(!scopes.has(Scope::PlaybooksRead));
+        assert_eq!("playbooks:write actors:read custom:thing", scopes.to_string());
+    }
+
+    #[test]
+    fn compares_scopes_by_name() {
+        let known = Scope::Other("playbooks:write".to_string());
+        assert_eq!(Scope::PlaybooksWrite, known);
+
+        let scopes = Scopes::from_iter([Scope::PlaybooksWrite, known.clone()]);
+        assert!(scopes.has(known));
+        assert_eq!("playbooks:write", scopes.to_string());
+    }
+
+    #[test]
+    fn serializes_as_a_string() {
+        let scopes: Scopes = serde_json::from_str(r#""account:read account:write""#).unwrap();
+
+        assert_eq!(
+            r#""account:read account:write""#,
+            serde_json::to_string(&scopes).unwrap()
+        );
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// A sensitive string, such as a token or a client secret.
+///
+/// It is printed as `[REDACTED]` by `Debug` and `Display` so it never ends up
+/// in logs, is wiped from memory when dropped, and still (de)serializes as the
+/// plain value so it can be sent to and received from the server.
+#[derive(Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new<S: Into<String>>(value: S) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the actual value, to be used only where it is really needed.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Secret;
+
+    #[test]
+    fn redacts_the_value() {
+        let secret = Secret::from("s3cr3t");
+
+        assert_eq!("[REDACTED]", format!("{secret:?}"));
+        assert_eq!("[REDACTED]", secret.to_string());
+        assert_eq!("s3cr3t", secret.expose());
+    }
+
+    #[test]
+    fn serializes_the_value() {
+        let secret: Secret = serde_json::from_str(r#""s3cr3t""#).unwrap();
+
+        assert_eq!(r#""s3cr3t""#, serde_json::to_string(&secret).unwrap());
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path as FsPath, PathBuf};
+
+use amp_common::sync::{EventKinds, Path, Synchronization};
+use tar::HeaderMode;
+
+use crate::errors::Error;
+
+/// How symbolic links are written into the sync payload.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkPolicy {
+    /// Store the link itself, so the actor gets the same symlink.
+    #[default]
+    Preserve,
+    /// Store the file or directory the link points to.
+    Follow,
+}
+
+/// Builds the `Synchronization` payload for `Actors::sync` from files on disk.
+///
+/// The payload is a tar archive of the given paths, relative to the workspace root.
+/// Unix permissions are kept, so executable scripts stay executable in the actor,
+/// empty directories are kept, and symlinks are handled according to the `LinkPolicy`.
+/// Paths that no longer exist, e.g. for a removal, are only listed and not archived.
+/// When links are followed, a link whose target is missing fails the build.
+///
+/// # Examples
+///
+/// ```no_run
+/// use amp_client::sync::{LinkPolicy, PayloadBuilder};
+/// use amp_common::sync::EventKinds;
+///
+/// let payload = PayloadBuilder::new("/path/to/workspace", EventKinds::Modify)
+///     .links(LinkPolicy::Preserve)
+///     .path("scripts/run.sh")
+///     .build()
+///     .unwrap();
+/// ```
+pub struct PayloadBuilder {
+    root: PathBuf,
+    kind: EventKinds,
+    links: LinkPolicy,
+    paths: Vec<PathBuf>,
+}
+
+impl PayloadBuilder {
+    /// Creates a builder for an event of `kind` in the workspace at `root`.
+    pub fn new<P: AsRef<FsPath>>(root: P, kind: EventKinds) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            kind,
+            links: LinkPolicy::default(),
+            paths: vec![],
+        }
+    }
+
+    /// Sets whether symbolic links are followed or preserved.
+    pub fn links(mut self, policy: LinkPolicy) -> Self {
+        self.links = policy;
+        self
+    }
+
+    /// Adds a path, relative to the workspace root, to the event.
+    pub fn path<P: AsRef<FsPath>>(mut self, path: P) -> Self {
+        self.paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Reads the paths from disk and assembles the `Synchronization`.
+    pub fn build(self) -> Result<Synchronization, Error> {
+        let follow = self.links == LinkPolicy::Follow;
+
+        let mut archive = tar::Builder::new(Vec::new());
+        archive.mode(HeaderMode::Complete);
+        archive.follow_symlinks(follow);
+
+        let mut paths = vec![];
+        let mut archived = false;
+
+        for relative in &self.paths {
+            let source = self.root.join(relative);
+            let name = relative.to_string_lossy().replace('\\', "/");
+
+            let metadata = if follow {
+                fs::metadata(&source)
+            } else {
+                fs::symlink_metadata(&source)
+            };
+            let metadata = match metadata {
+                Ok(metadata) => metadata,
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    // Following a link to nothing must not look like a removal of the link.
+                    if follow && fs::symlink_metadata(&source).is_ok() {
+                        return Err(Error::Validation(format!(
+                            "{name:?} is a symbolic link to a missing file"
+                        )));
+                    }
+                    paths.push(Path::File(name));
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            if metadata.is_dir() {
+                archive.append_dir_all(relative, &source)?;
+                paths.push(Path::Directory(name));
+            } else {
+                archive.append_path_with_name(&source, relative)?;
+                paths.push(Path::File(name));
+            }
+            archived = true;
+        }
+
+        let payload = archive.into_inner()?;
+
+        Ok(Synchronization {
+            kind: self.kind,
+            paths,
+            attributes: None,
+            payload: archived.then_some(payload),
+        })
+    }
+}
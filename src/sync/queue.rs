@@ -0,0 +1,447 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path as FsPath, PathBuf};
+
+use amp_common::sync::{EventKinds, Path, Synchronization};
+use serde::{Deserialize, Serialize};
+
+use crate::actors::Actors;
+use crate::errors::Error;
+use crate::fs::write_private;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    organization: Option<String>,
+    pid: String,
+    name: String,
+    batch: Synchronization,
+}
+
+impl Entry {
+    fn belongs_to(&self, organization: Option<&str>, pid: &str, name: &str) -> bool {
+        self.organization.as_deref() == organization && self.pid == pid && self.name == name
+    }
+}
+
+/// A batch the server refused for good, kept aside so it doesn't block the queue.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Rejected {
+    /// The organization of the actor, `None` for the personal account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// The ID of the playbook
+    pub pid: String,
+    /// The name of the actor
+    pub name: String,
+    /// The batch that was refused
+    pub batch: Synchronization,
+    /// The error the server refused the batch with
+    pub error: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct State {
+    entries: VecDeque<Entry>,
+    #[serde(default)]
+    rejected: Vec<Rejected>,
+    last_error: Option<String>,
+}
+
+/// A durable queue in front of `Actors::sync`.
+///
+/// Pending `Synchronization` batches are written to disk before they are sent,
+/// so events produced while the server is unreachable survive a restart and are
+/// delivered in their original order once the connection is back.
+///
+/// # Examples
+///
+/// ```no_run
+/// use amp_client::client::Client;
+/// use amp_client::sync::SyncQueue;
+/// use amp_common::sync::{EventKinds, Path, Synchronization};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let token = Some(String::from("AUTH_TOKEN"));
+///     let client = Client::new("https://cloud.amphitheatre.app", token);
+///     let mut queue = SyncQueue::open(".amp/sync-queue.json").unwrap();
+///
+///     let payload = Synchronization {
+///         kind: EventKinds::Modify,
+///         paths: vec![Path::File(String::from("main.go"))],
+///         attributes: None,
+///         payload: None,
+///     };
+///
+///     if let Err(err) = queue.sync(&client.actors(), "1", "hello", payload).await {
+///         println!("{} batches pending: {err}", queue.len());
+///     }
+/// }
+/// ```
+pub struct SyncQueue {
+    path: PathBuf,
+    state: State,
+}
+
+impl SyncQueue {
+    /// Opens the queue stored at `path`, creating an empty one if the file does not exist yet.
+    pub fn open<P: AsRef<FsPath>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let state = load(&path)?;
+
+        Ok(Self { path, state })
+    }
+
+    /// Returns the number of batches waiting to be sent.
+    pub fn len(&self) -> usize {
+        self.state.entries.len()
+    }
+
+    /// Returns `true` if there is nothing waiting to be sent.
+    pub fn is_empty(&self) -> bool {
+        self.state.entries.is_empty()
+    }
+
+    /// Returns the error of the last failed flush, cleared once a flush succeeds.
+    pub fn last_error(&self) -> Option<&str> {
+        self.state.last_error.as_deref()
+    }
+
+    /// Returns the batches the server refused for good, which are no longer retried.
+    pub fn rejected(&self) -> &[Rejected] {
+        &self.state.rejected
+    }
+
+    /// Removes the refused batches from the queue and returns them.
+    pub fn take_rejected(&mut self) -> Result<Vec<Rejected>, Error> {
+        let rejected = std::mem::take(&mut self.state.rejected);
+        self.persist()?;
+        Ok(rejected)
+    }
+
+    /// Appends a batch for the given actor and persists the queue.
+    ///
+    /// The organization of `actors` is stored along with the batch, so that it is
+    /// sent to the same organization whichever `Actors` flushes the queue.
+    ///
+    /// Pending events of the same actor that touch a path also present in `batch`
+    /// are merged with it, unless a rename of that path or of a directory above it
+    /// sits in between: a creation followed by a change is still sent as a creation,
+    /// a removal followed by a creation as a change, and a creation followed by a
+    /// removal is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// `actors`: The `Actors` service of the organization the actor belongs to
+    /// `pid`: The ID of the playbook
+    /// `name`: The name of the actor
+    /// `batch`: The `Synchronization` to enqueue
+    pub fn push(
+        &mut self,
+        actors: &Actors<'_>,
+        pid: &str,
+        name: &str,
+        batch: Synchronization,
+    ) -> Result<(), Error> {
+        let organization = actors.organization.as_deref();
+        let batches = if matches!(batch.kind, EventKinds::Rename) {
+            vec![batch]
+        } else {
+            self.collapse(organization, pid, name, batch)?
+        };
+
+        for batch in batches {
+            self.state.entries.push_back(Entry {
+                organization: organization.map(str::to_string),
+                pid: pid.to_string(),
+                name: name.to_string(),
+                batch,
+            });
+        }
+        self.persist()
+    }
+
+    /// Sends the pending batches in order, stopping at the first failure.
+    ///
+    /// Each batch is removed from the queue only after the server accepted it.
+    /// A batch refused as malformed (400), too large (413) or invalid (422) would
+    /// be refused again, it is moved to `rejected` and the flush goes on. On any
+    /// other failure, including an expired token, missing permissions or an actor
+    /// that doesn't exist yet, the batch and everything behind it stay queued for
+    /// the next flush. Returns the number of batches that were sent.
+    ///
+    /// Each batch is sent to the organization it was pushed for, `actors` only
+    /// provides the client to send them with.
+    pub async fn flush(&mut self, actors: &Actors<'_>) -> Result<usize, Error> {
+        let mut flushed = 0;
+
+        while let Some(entry) = self.state.entries.front() {
+            let scoped = Actors {
+                client: actors.client,
+                organization: entry.organization.clone(),
+            };
+            match scoped.send_sync(&entry.pid, &entry.name, &entry.batch).await {
+                Ok(()) => {
+                    self.state.entries.pop_front();
+                    flushed += 1;
+                    self.state.last_error = None;
+                    self.persist()?;
+                }
+                Err(err) if is_permanent(&err) => {
+                    if let Some(entry) = self.state.entries.pop_front() {
+                        self.state.rejected.push(Rejected {
+                            organization: entry.organization,
+                            pid: entry.pid,
+                            name: entry.name,
+                            batch: entry.batch,
+                            error: err.to_string(),
+                        });
+                    }
+                    self.state.last_error = Some(err.to_string());
+                    self.persist()?;
+                }
+                Err(err) => {
+                    self.state.last_error = Some(err.to_string());
+                    self.persist()?;
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(flushed)
+    }
+
+    /// Enqueues the batch and then tries to flush the whole queue.
+    ///
+    /// An error means the server could not be reached or failed,
+    /// the pending batches are kept and will be retried by the next flush.
+    ///
+    /// # Arguments
+    ///
+    /// `actors`: The `Actors` service used to send the batches
+    /// `pid`: The ID of the playbook
+    /// `name`: The name of the actor
+    /// `payload`: The `Synchronization` to send
+    pub async fn sync(
+        &mut self,
+        actors: &Actors<'_>,
+        pid: &str,
+        name: &str,
+        payload: Synchronization,
+    ) -> Result<usize, Error> {
+        self.push(actors, pid, name, payload)?;
+        self.flush(actors).await
+    }
+
+    /// Merges `batch` with the pending events of the same actor touching the same paths,
+    /// returning the batches left to enqueue.
+    fn collapse(
+        &mut self,
+        organization: Option<&str>,
+        pid: &str,
+        name: &str,
+        batch: Synchronization,
+    ) -> Result<Vec<Synchronization>, Error> {
+        let mut targets: HashSet<String> = batch.paths.iter().map(|p| key(p).to_string()).collect();
+        let mut created = HashSet::new();
+        let mut modified = HashSet::new();
+        let mut cancelled = HashSet::new();
+        let mut reduced = vec![];
+
+        for (index, entry) in self.state.entries.iter().enumerate().rev() {
+            if targets.is_empty() {
+                break;
+            }
+            if !entry.belongs_to(organization, pid, name) {
+                continue;
+            }
+
+            // Events before a rename must be replayed as they were, for the renamed
+            // paths and for everything under them.
+            if matches!(entry.batch.kind, EventKinds::Rename) {
+                targets.retain(|t| {
+                    !entry
+                        .batch
+                        .paths
+                        .iter()
+                        .map(key)
+                        .any(|p| is_within(t, p) || is_within(p, t))
+                });
+                continue;
+            }
+
+            let touched: HashSet<String> = entry
+                .batch
+                .paths
+                .iter()
+                .map(key)
+                .filter(|p| targets.contains(*p))
+                .map(str::to_string)
+                .collect();
+            if touched.is_empty() {
+                continue;
+            }
+            targets.retain(|p| !touched.contains(p));
+
+            match merge(&entry.batch.kind, &batch.kind) {
+                Merge::Barrier => continue,
+                Merge::Keep => {}
+                Merge::Create => created.extend(touched.iter().cloned()),
+                Merge::Modify => modified.extend(touched.iter().cloned()),
+                Merge::Cancel => cancelled.extend(touched.iter().cloned()),
+            }
+            reduced.push((index, select(&entry.batch, None, |p| !touched.contains(p))?));
+        }
+
+        let mut batches = vec![];
+        let rest = select(&batch, None, |p| {
+            !created.contains(p) && !modified.contains(p) && !cancelled.contains(p)
+        })?;
+        if !rest.paths.is_empty() || batch.paths.is_empty() {
+            batches.push(rest);
+        }
+        if !created.is_empty() {
+            batches.push(select(&batch, Some(EventKinds::Create), |p| created.contains(p))?);
+        }
+        if !modified.is_empty() {
+            batches.push(select(&batch, Some(EventKinds::Modify), |p| {
+                modified.contains(p)
+            })?);
+        }
+
+        // Every batch was rebuilt, the queue can be changed without failing halfway.
+        for (index, batch) in reduced {
+            self.state.entries[index].batch = batch;
+        }
+        self.state
+            .entries
+            .retain(|e| !e.belongs_to(organization, pid, name) || !e.batch.paths.is_empty());
+
+        Ok(batches)
+    }
+
+    fn persist(&self) -> Result<(), Error> {
+        write_private(&self.path, &serde_json::to_vec(&self.state)?)?;
+        Ok(())
+    }
+}
+
+fn load(path: &FsPath) -> Result<State, Error> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(State::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// How a pending event and a newer event of the same path combine.
+enum Merge {
+    /// The newer event replaces the pending one.
+    Keep,
+    /// Both are replaced by a creation with the content of the newer one.
+    Create,
+    /// Both are replaced by a change with the content of the newer one.
+    Modify,
+    /// Both are dropped, the path never has to reach the actor.
+    Cancel,
+    /// Both have to be sent as they are.
+    Barrier,
+}
+
+fn merge(pending: &EventKinds, newer: &EventKinds) -> Merge {
+    use EventKinds::{Create, Modify, Remove};
+
+    match (pending, newer) {
+        (Create, Create) | (Modify, Create | Modify | Remove) | (Remove, Remove) => Merge::Keep,
+        (Create, Modify) => Merge::Create,
+        (Remove, Create | Modify) => Merge::Modify,
+        (Create, Remove) => Merge::Cancel,
+        _ => Merge::Barrier,
+    }
+}
+
+/// Returns the part of `batch` whose paths match `keep`, with the archive of those
+/// paths only, and with `kind` instead of its kind if given.
+fn select<F>(batch: &Synchronization, kind: Option<EventKinds>, keep: F) -> Result<Synchronization, Error>
+where
+    F: Fn(&str) -> bool,
+{
+    let paths: Vec<Path> = batch.paths.iter().filter(|p| keep(key(p))).cloned().collect();
+    let payload = match &batch.payload {
+        Some(payload) => archive(payload, &paths)?,
+        None => None,
+    };
+
+    Ok(Synchronization {
+        kind: kind.unwrap_or_else(|| batch.kind.clone()),
+        paths,
+        attributes: batch.attributes.clone(),
+        payload,
+    })
+}
+
+/// Rebuilds the tar `payload` with the entries of `paths` and their contents only,
+/// `None` if none of them was archived.
+fn archive(payload: &[u8], paths: &[Path]) -> Result<Option<Vec<u8>>, Error> {
+    let mut source = tar::Archive::new(payload);
+    let mut archive = tar::Builder::new(Vec::new());
+    let mut archived = false;
+
+    for entry in source.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().trim_end_matches('/').to_string();
+        let retained = paths.iter().map(key).any(|p| is_within(&name, p));
+        if !retained {
+            continue;
+        }
+
+        let mut header = entry.header().clone();
+        match entry.link_name()?.map(|target| target.into_owned()) {
+            Some(target) => archive.append_link(&mut header, &name, target)?,
+            None => archive.append_data(&mut header, &name, &mut entry)?,
+        }
+        archived = true;
+    }
+
+    if !archived {
+        return Ok(None);
+    }
+    Ok(Some(archive.into_inner()?))
+}
+
+/// Returns `true` if the server refused the batch itself, so that sending it again
+/// can only fail the same way.
+fn is_permanent(err: &Error) -> bool {
+    matches!(err, Error::Api { status, .. } if matches!(status, 400 | 413 | 422))
+}
+
+/// Returns `true` if `path` is `parent` or a path under it.
+fn is_within(path: &str, parent: &str) -> bool {
+    let (path, parent) = (path.trim_end_matches('/'), parent.trim_end_matches('/'));
+    path == parent
+        || path
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn key(path: &Path) -> &str {
+    match path {
+        Path::File(p) | Path::Directory(p) => p,
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A point in time as sent by the server.
+///
+/// Timestamps are kept the way they were received, so serializing a resource
+/// gives back exactly what the server sent. The server mostly uses RFC 3339
+/// strings, but some endpoints send Unix seconds instead, both are accepted.
+///
+/// With the `chrono` feature enabled, timestamps can be converted to
+/// `chrono::DateTime<Utc>`, ordered and aged.
+///
+/// # Examples
+///
+/// ```
+/// use amp_client::timestamp::Timestamp;
+///
+/// let timestamp: Timestamp = serde_json::from_str(r#""2016-06-09T20:03:39Z""#).unwrap();
+///
+/// assert_eq!("2016-06-09T20:03:39Z", timestamp.to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Timestamp {
+    repr: Repr,
+    /// The instant denoted by `repr`, parsed once when the timestamp is built.
+    #[cfg(feature = "chrono")]
+    datetime: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Repr {
+    Text(String),
+    Seconds(i64),
+}
+
+impl Timestamp {
+    fn new(repr: Repr) -> Self {
+        Timestamp {
+            #[cfg(feature = "chrono")]
+            datetime: match &repr {
+                Repr::Seconds(secs) => Utc.timestamp_opt(*secs, 0).single(),
+                Repr::Text(text) => parse(text.trim()),
+            },
+            repr,
+        }
+    }
+
+    /// Returns the timestamp as a UTC date-time, or `None` if it can't be parsed.
+    ///
+    /// Besides RFC 3339, the parsing accepts a space instead of the `T`, a missing
+    /// offset (taken as UTC), a bare date (taken as midnight UTC) and Unix seconds.
+    #[cfg(feature = "chrono")]
+    pub fn to_datetime(&self) -> Option<DateTime<Utc>> {
+        self.datetime
+    }
+
+    /// Returns the time elapsed since the timestamp, negative if it lies in the future.
+    #[cfg(feature = "chrono")]
+    pub fn age(&self) -> Option<chrono::Duration> {
+        self.to_datetime().map(|datetime| Utc::now() - datetime)
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn parse(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%d %H:%M:%S%.f%z"] {
+        if let Ok(datetime) = DateTime::parse_from_str(text, format) {
+            return Some(datetime.with_timezone(&Utc));
+        }
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(datetime.and_utc());
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).map(|datetime| datetime.and_utc());
+    }
+    text.parse()
+        .ok()
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        Timestamp {
+            repr: Repr::Text(datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            datetime: Some(datetime),
+        }
+    }
+}
+
+/// Timestamps are ordered by the instant they denote, those which can't be
+/// parsed come first, ties are broken by the way they were sent, so that only
+/// equal timestamps compare as `Equal`.
+#[cfg(feature = "chrono")]
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.datetime, &self.repr).cmp(&(other.datetime, &other.repr))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<String> for Timestamp {
+    fn from(text: String) -> Self {
+        Timestamp::new(Repr::Text(text))
+    }
+}
+
+impl From<&str> for Timestamp {
+    fn from(text: &str) -> Self {
+        Timestamp::new(Repr::Text(text.to_string()))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Text(text) => f.write_str(text),
+            Repr::Seconds(secs) => write!(f, "{secs}"),
+        }
+    }
+}
+
+impl PartialEq<&str> for Timestamp {
+    fn eq(&self, other: &&str) -> bool {
+        matches!(&self.repr, Repr::Text(text) if text == other)
+    }
+}
+
+impl PartialEq<Timestamp> for &str {
+    fn eq(&self, other: &Timestamp) -> bool {
+        other == self
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.repr {
+            Repr::Text(text) => serializer.serialize_str(text),
+            Repr::Seconds(secs) => serializer.serialize_i64(*secs),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Seconds(i64),
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => Timestamp::new(Repr::Text(text)),
+            Raw::Seconds(secs) => Timestamp::new(Repr::Seconds(secs)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timestamp;
+
+    #[test]
+    fn round_trips_what_the_server_sent() {
+        for json in [
+            r#""2016-06-09T20:03:39Z""#,
+            r#""2016-06-09 20:03:39""#,
+            "1465502619",
+        ] {
+            let timestamp: Timestamp = serde_json::from_str(json).unwrap();
+            assert_eq!(json, serde_json::to_string(&timestamp).unwrap());
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn parses_leniently() {
+        let expected = "2016-06-09T20:03:39Z"
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap();
+
+        for text in [
+            "2016-06-09T20:03:39Z",
+            "2016-06-09T22:03:39+02:00",
+            "2016-06-09 20:03:39",
+            "2016-06-09T20:03:39",
+            "1465502619",
+        ] {
+            assert_eq!(Some(expected), Timestamp::from(text).to_datetime(), "{text}");
+        }
+
+        let seconds: Timestamp = serde_json::from_str("1465502619").unwrap();
+        assert_eq!(Some(expected), seconds.to_datetime());
+        assert_eq!(None, Timestamp::from("yesterday").to_datetime());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn orders_by_instant() {
+        let mut timestamps = vec![
+            Timestamp::from("2016-06-09T22:03:40+02:00"),
+            Timestamp::from("2016-06-09T20:03:41Z"),
+            Timestamp::from("2016-06-09 20:03:39"),
+        ];
+        timestamps.sort();
+
+        assert_eq!("2016-06-09 20:03:39", timestamps[0]);
+        assert_eq!("2016-06-09T22:03:40+02:00", timestamps[1]);
+        assert_eq!("2016-06-09T20:03:41Z", timestamps[2]);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn orders_consistently_with_equality() {
+        let text = Timestamp::from("1465502619");
+        let seconds: Timestamp = serde_json::from_str("1465502619").unwrap();
+
+        assert_eq!(text.to_datetime(), seconds.to_datetime());
+        assert_ne!(text, seconds);
+        assert_ne!(std::cmp::Ordering::Equal, text.cmp(&seconds));
+        assert_eq!(std::cmp::Ordering::Equal, text.cmp(&text.clone()));
+    }
+}
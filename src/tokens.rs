@@ -0,0 +1,136 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amp_common::http::{
+    endpoint::{Empty, Endpoint},
+    HTTPError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::scopes::Scopes;
+use crate::secret::Secret;
+use crate::timestamp::Timestamp;
+
+/// A long-lived token of the current account, meant for scripts and CI.
+///
+/// The secret of the token is never part of it, the server only returns
+/// it once, in the `CreatedToken` of `Tokens::create`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PersonalAccessToken {
+    /// The token ID
+    pub id: u64,
+    /// The name given to the token
+    pub name: String,
+    /// The scopes granted to the token
+    pub scope: Scopes,
+    /// When the token stops being accepted, `None` if it never expires
+    pub expires_at: Option<Timestamp>,
+    /// When the token was last used to authenticate a request
+    pub last_used_at: Option<Timestamp>,
+    /// When the token was created in Amphitheatre
+    pub created_at: Timestamp,
+}
+
+/// A newly created personal access token along with its secret.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreatedToken {
+    #[serde(flatten)]
+    pub token: PersonalAccessToken,
+    /// The secret to authenticate with, it can't be retrieved again
+    pub secret: Secret,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TokenPayload {
+    /// The name of the token
+    pub name: String,
+    /// The scopes granted to the token, the server defaults if empty
+    #[serde(default, skip_serializing_if = "Scopes::is_empty")]
+    pub scope: Scopes,
+    /// When the token stops being accepted (RFC 3339), `None` for a token that never expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Timestamp>,
+}
+
+struct TokenEndpoint;
+
+impl Endpoint for TokenEndpoint {
+    type Output = CreatedToken;
+}
+
+struct TokensEndpoint;
+
+impl Endpoint for TokensEndpoint {
+    type Output = Vec<PersonalAccessToken>;
+}
+
+/// The Tokens Service handles the personal access tokens endpoint of the Amphitheatre API.
+///
+/// See [API Documentation: token](https://docs.amphitheatre.app/api/token)
+pub struct Tokens<'a> {
+    pub client: &'a Client,
+}
+
+impl Tokens<'_> {
+    /// Lists the personal access tokens of the current account.
+    pub async fn list(&self) -> Result<Vec<PersonalAccessToken>, HTTPError> {
+        let res = self.client.get::<TokensEndpoint>("/me/tokens", None).await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Create a personal access token for the current account.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use amp_client::client::Client;
+    /// use amp_client::scopes::Scope;
+    /// use amp_client::tokens::TokenPayload;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = Some(String::from("AUTH_TOKEN"));
+    ///     let client = Client::new("https://cloud.amphitheatre.app", token);
+    ///     let payload = TokenPayload {
+    ///         name: String::from("ci"),
+    ///         scope: [Scope::PlaybooksRead, Scope::PlaybooksWrite].into_iter().collect(),
+    ///         expires_at: None,
+    ///     };
+    ///     let created = client.tokens().create(payload).await.unwrap();
+    ///     println!("{}", created.secret.expose());
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// `payload`: The `TokenPayload` with the name, scopes and expiry of the token
+    pub async fn create(&self, payload: TokenPayload) -> Result<CreatedToken, HTTPError> {
+        let res = self
+            .client
+            .post::<TokenEndpoint, TokenPayload>("/me/tokens", &payload)
+            .await?;
+        Ok(res.data.unwrap())
+    }
+
+    /// Revoke a personal access token, requests using it are rejected from then on.
+    ///
+    /// # Arguments
+    ///
+    /// `id`: The ID of the token
+    pub async fn revoke(&self, id: u64) -> Result<u16, HTTPError> {
+        let path = format!("/me/tokens/{id}");
+        Ok(self.client.delete::<Empty>(&path).await?.status)
+    }
+}
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use common::mock;
+use amp_client::accounts::{AccountPayload, AuditAction, AuditLogFilter, AuditTarget, PasswordPayload};
+use common::{client, mock, route};
+use futures::TryStreamExt;
+use mockito::{Matcher, Server};
 
 mod common;
 
@@ -25,4 +28,150 @@ async fn me_success_with_account() {
     assert_eq!(1, account.id);
     assert_eq!("example-account@example.com", account.email);
     assert_eq!("example-account", account.name);
+    assert_eq!("2015-09-18T23:04:37Z", account.created_at);
+    assert_eq!("2016-06-09T20:03:39Z", account.updated_at);
+}
+
+#[tokio::test]
+async fn update_me_success() {
+    let setup = mock("/me", "accounts/update-me-success", "PATCH").await;
+    let client = setup.0;
+    let payload = AccountPayload {
+        name: Some("New Name".to_string()),
+        email: Some("new-email@example.com".to_string()),
+    };
+    let account = client.accounts().update_me(payload).await.unwrap();
+
+    assert_eq!("New Name", account.name);
+    assert_eq!("new-email@example.com", account.email);
+}
+
+#[tokio::test]
+async fn change_password_success() {
+    let setup = mock("/me/password", "accounts/change-password-success", "POST").await;
+    let client = setup.0;
+    let payload = PasswordPayload {
+        current_password: "old-password".into(),
+        password: "new-password".into(),
+    };
+    let status = client.accounts().change_password(payload).await.unwrap();
+
+    assert_eq!(204, status);
+}
+
+#[tokio::test]
+async fn send_email_verification_success() {
+    let setup = mock(
+        "/me/email/verification",
+        "accounts/send-email-verification-success",
+        "POST",
+    )
+    .await;
+    let client = setup.0;
+    let status = client.accounts().send_email_verification().await.unwrap();
+
+    assert_eq!(202, status);
+}
+
+#[tokio::test]
+async fn verify_email_success() {
+    let setup = mock("/me/email/verify", "accounts/verify-email-success", "POST").await;
+    let client = setup.0;
+    let account = client
+        .accounts()
+        .verify_email("verification-token")
+        .await
+        .unwrap();
+
+    assert_eq!("new-email@example.com", account.email);
+}
+
+#[tokio::test]
+async fn usage_success() {
+    let setup = mock("/me/usage", "accounts/get-usage-success", "GET").await;
+    let client = setup.0;
+    let usage = client.accounts().usage().await.unwrap();
+
+    assert!(usage.playbooks.is_exhausted());
+    assert!(!usage.playbooks.allows(1));
+    assert_eq!(Some(13), usage.actors.remaining());
+    assert_eq!(Some(0.875), usage.cpu.utilization());
+    assert_eq!(Some(0.75), usage.memory.utilization());
+    assert_eq!(None, usage.storage.limit);
+    assert!(usage.storage.allows(u64::MAX));
+    assert_eq!(vec!["playbooks"], usage.exhausted());
+}
+
+#[tokio::test]
+async fn audit_log_success() {
+    let setup = mock("/me/audit_log", "accounts/list-audit-events-success", "GET").await;
+    let client = setup.0;
+    let events: Vec<_> = client
+        .accounts()
+        .audit_log(AuditLogFilter::new())
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(3, events.len());
+    assert_eq!(AuditAction::PlaybookDelete, events[0].action);
+    assert_eq!("example-account@example.com", events[0].actor.email);
+    assert_eq!(Some("203.0.113.7".to_string()), events[0].source_ip);
+    assert_eq!(
+        AuditTarget::Actor {
+            pid: "a82abba3-df2f-4608-b1a5-9e058ff80468".to_string(),
+            name: "amp-example-go".to_string(),
+        },
+        events[1].target
+    );
+    assert_eq!(AuditAction::Other("billing.update".to_string()), events[2].action);
+    assert_eq!(AuditTarget::Other, events[2].target);
+}
+
+#[tokio::test]
+async fn audit_log_fetches_pages_lazily_with_filters() {
+    let event = |id: u64| {
+        format!(
+            r#"{{"id":{id},"actor":{{"id":1,"email":"example-account@example.com","name":"example-account"}},"action":"playbook.stop","target":{{"type":"playbook","id":"1"}},"source_ip":null,"created_at":"2016-01-19T21:49:42Z"}}"#
+        )
+    };
+
+    let mut server = Server::new_async().await;
+    let mut pages = Vec::new();
+    for (page, body) in [
+        ("1", format!("[{},{}]", event(3), event(2))),
+        ("2", format!("[{}]", event(1))),
+    ] {
+        let mock = route(&mut server, "GET", "/me/audit_log", 200, &body)
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("page".into(), page.into()),
+                Matcher::UrlEncoded("per_page".into(), "2".into()),
+                Matcher::UrlEncoded("action".into(), "playbook.stop".into()),
+                Matcher::UrlEncoded("target_type".into(), "playbook".into()),
+                Matcher::UrlEncoded("target_id".into(), "1".into()),
+                Matcher::UrlEncoded("since".into(), "2016-01-01T00:00:00Z".into()),
+            ]))
+            .create_async()
+            .await;
+        pages.push(mock);
+    }
+
+    let client = client(&server);
+    let filter = AuditLogFilter::new()
+        .since("2016-01-01T00:00:00Z")
+        .action(AuditAction::PlaybookStop)
+        .playbook("1")
+        .per_page(2);
+    let mut events = Box::pin(client.accounts().audit_log(filter));
+
+    assert_eq!(3, events.try_next().await.unwrap().unwrap().id);
+    assert!(pages[0].matched_async().await);
+    assert!(!pages[1].matched_async().await);
+
+    assert_eq!(2, events.try_next().await.unwrap().unwrap().id);
+    assert_eq!(1, events.try_next().await.unwrap().unwrap().id);
+    assert!(events.try_next().await.unwrap().is_none());
+    for page in pages {
+        page.assert_async().await;
+    }
 }
@@ -12,11 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use amp_client::options::ListOptions;
 use amp_common::sync::{EventKinds, Path, Synchronization};
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
+use mockito::{Matcher, Server};
 use reqwest_eventsource::Event;
 
-use crate::common::mock;
+use crate::common::{client, fixture_response, mock, route};
 mod common;
 
 #[tokio::test]
@@ -123,3 +125,67 @@ async fn sync_actor_test() {
     assert!(response.is_ok());
     assert_eq!(202, response.unwrap());
 }
+
+#[tokio::test]
+async fn list_stream_actors_stops_on_an_empty_page_test() {
+    let (_, items) = fixture_response("actors/list-actors-success");
+
+    let mut server = Server::new_async().await;
+    let mut pages = Vec::new();
+    for (page, body) in [("1", items), ("2", "[]".to_string())] {
+        let mock = route(&mut server, "GET", "/playbooks/1/actors", 200, &body)
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("page".into(), page.into()),
+                Matcher::UrlEncoded("per_page".into(), "1".into()),
+            ]))
+            .create_async()
+            .await;
+        pages.push(mock);
+    }
+
+    let client = client(&server);
+    let mut actors = Box::pin(
+        client
+            .actors()
+            .list_stream("1", Some(ListOptions::new().per_page(1))),
+    );
+
+    assert_eq!("amp-example-go", actors.try_next().await.unwrap().unwrap().name);
+    assert!(!pages[1].matched_async().await);
+    assert!(actors.try_next().await.unwrap().is_none());
+    for page in pages {
+        page.assert_async().await;
+    }
+}
+
+#[tokio::test]
+async fn list_stream_actors_stops_on_a_short_page_test() {
+    let (_, items) = fixture_response("actors/list-actors-success");
+
+    let mut server = Server::new_async().await;
+    let first = route(&mut server, "GET", "/playbooks/1/actors", 200, &items)
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("page".into(), "1".into()),
+            Matcher::UrlEncoded("per_page".into(), "2".into()),
+        ]))
+        .expect(1)
+        .create_async()
+        .await;
+    let second = route(&mut server, "GET", "/playbooks/1/actors", 200, "[]")
+        .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+        .expect(0)
+        .create_async()
+        .await;
+
+    let client = client(&server);
+    let actors: Vec<_> = client
+        .actors()
+        .list_stream("1", Some(ListOptions::new().per_page(2)))
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(1, actors.len());
+    first.assert_async().await;
+    second.assert_async().await;
+}
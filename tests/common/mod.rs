@@ -17,7 +17,7 @@
 use std::fs;
 
 use amp_client::client::Client;
-use mockito::{Server, ServerGuard};
+use mockito::{Matcher, Mock, Server, ServerGuard};
 
 /// Creates a mock server and a client (changing the url of the client
 /// to that of the mock server to capture the requests).
@@ -25,28 +25,70 @@ use mockito::{Server, ServerGuard};
 /// It builds a response struct for the mock server using the fixture.
 #[allow(dead_code)]
 pub async fn mock(path: &str, fixture: &str, method: &str) -> (Client, ServerGuard) {
-    let path = format!("/v1{path}");
-    let (status, body) = parse_fixture(fixture);
+    let (status, body) = fixture_response(fixture);
 
     let mut server = Server::new_async().await;
+    route(&mut server, method, path, status, &body)
+        .match_query(Matcher::Any)
+        .create_async()
+        .await;
+
+    (client(&server), server)
+}
+
+/// Like `mock`, but the server only answers the requests whose body matches `body`.
+///
+/// The returned mock can be asserted to check the request was actually sent.
+#[allow(dead_code)]
+pub async fn mock_with_body(
+    path: &str,
+    fixture: &str,
+    method: &str,
+    body: Matcher,
+) -> (Client, ServerGuard, Mock) {
+    let (status, response) = fixture_response(fixture);
+
+    let mut server = Server::new_async().await;
+    let mock = route(&mut server, method, path, status, &response)
+        .match_query(Matcher::Any)
+        .match_body(body)
+        .create_async()
+        .await;
+
+    (client(&server), server, mock)
+}
+
+/// Starts a mock of the API `path` on `server`, answering with `status`, `body`
+/// and the rate limit headers of the API.
+///
+/// The mock can be narrowed with more matchers before it is created, for the
+/// tests which need several routes on the same server.
+#[allow(dead_code)]
+pub fn route(server: &mut ServerGuard, method: &str, path: &str, status: usize, body: &str) -> Mock {
     server
-        .mock(method, path.as_str())
-        .match_query(mockito::Matcher::Any)
+        .mock(method, format!("/v1{path}").as_str())
         .with_header("x-ratelimit-limit", "2")
         .with_header("x-ratelimit-remaining", "2")
         .with_header("x-ratelimit-after", "never")
         .with_status(status)
         .with_body(body)
-        .create_async()
-        .await;
+}
 
-    let base_url = format!("{}/v1", server.url());
-    let client = Client::new(&base_url, Some("some-token".to_string()));
+/// Returns the base URL of the API mocked by `server`.
+#[allow(dead_code)]
+pub fn base_url(server: &ServerGuard) -> String {
+    format!("{}/v1", server.url())
+}
 
-    (client, server)
+/// Creates a client of the API mocked by `server`, authenticated with `some-token`.
+#[allow(dead_code)]
+pub fn client(server: &ServerGuard) -> Client {
+    Client::new(&base_url(server), Some("some-token".to_string()))
 }
 
-fn parse_fixture(fixture: &str) -> (usize, String) {
+/// Reads the status and the body of the response recorded in `fixture`.
+#[allow(dead_code)]
+pub fn fixture_response(fixture: &str) -> (usize, String) {
     let fixture = format!("./tests/fixtures/v1/api/{fixture}.http");
 
     let content = fs::read_to_string(fixture.as_str()).expect("Something went wrong: Couldn't read the file");
@@ -12,8 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amp_client::oauth::OAuthTokenPayload;
-use common::mock;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use amp_client::client::Client;
+use amp_client::errors::Error;
+use amp_client::oauth::{
+    AccessToken, AuthorizationRequest, DeviceAuthorizationPayload, OAuthClientCredentialsPayload,
+    OAuthPkceTokenPayload, OAuthRefreshTokenPayload, OAuthTokenPayload, EXPIRY_MARGIN,
+};
+use amp_client::scopes::{Scope, Scopes};
+use common::{base_url, mock, mock_with_body, route};
+use mockito::{Matcher, Server};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
 
 mod common;
 
@@ -24,8 +36,8 @@ async fn exchange_authorization_for_token_test() {
 
     let payload = OAuthTokenPayload {
         client_id: "id".to_string(),
-        client_secret: "secret".to_string(),
-        code: "code".to_string(),
+        client_secret: "secret".into(),
+        code: "code".into(),
         redirect_uri: "/redirect_uri".to_string(),
         state: "state".to_string(),
     };
@@ -37,8 +49,463 @@ async fn exchange_authorization_for_token_test() {
         }
     };
 
-    assert_eq!("zKQ7OLqF5N1gylcJweA9WodA000BUNJD", access_token.access_token);
+    assert_eq!(
+        "zKQ7OLqF5N1gylcJweA9WodA000BUNJD",
+        access_token.access_token.expose()
+    );
     assert_eq!("Bearer", access_token.token_type);
     assert_eq!(None, access_token.scope);
     assert_eq!(1, access_token.account_id);
 }
+
+#[tokio::test]
+async fn exchange_authorization_with_verifier_test() {
+    let (client, _server, exchange) = mock_with_body(
+        "/oauth/access_token",
+        "oauth/access-token-success",
+        "POST",
+        Matcher::PartialJsonString(
+            r#"{"grant_type":"authorization_code","code":"code","code_verifier":"verifier"}"#.to_string(),
+        ),
+    )
+    .await;
+
+    let payload = OAuthPkceTokenPayload {
+        client_id: "id".to_string(),
+        code: "code".into(),
+        code_verifier: "verifier".into(),
+        redirect_uri: "/redirect_uri".to_string(),
+        state: "state".to_string(),
+    };
+
+    let access_token = client
+        .oauth()
+        .exchange_authorization_with_verifier(payload)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        "zKQ7OLqF5N1gylcJweA9WodA000BUNJD",
+        access_token.access_token.expose()
+    );
+    assert_eq!(1, access_token.account_id);
+    exchange.assert_async().await;
+}
+
+#[test]
+fn authorize_url_test() {
+    let client = Client::new("https://cloud.amphitheatre.app/v1", None);
+    let request = AuthorizationRequest::new("id")
+        .redirect_uri("http://localhost:8080/callback")
+        .scope(Scope::PlaybooksRead);
+
+    let authorization = client.oauth().authorize_url(request).unwrap();
+    let url = url::Url::parse(&authorization.url).unwrap();
+    let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+    assert_eq!("/v1/oauth/authorize", url.path());
+    assert_eq!("code", query["response_type"]);
+    assert_eq!("id", query["client_id"]);
+    assert_eq!("http://localhost:8080/callback", query["redirect_uri"]);
+    assert_eq!("playbooks:read", query["scope"]);
+    assert_eq!(authorization.state, query["state"]);
+    assert_eq!("S256", query["code_challenge_method"]);
+
+    let verifier = authorization.code_verifier.unwrap();
+    assert_eq!(43, verifier.expose().len());
+    assert_ne!(verifier.expose(), query["code_challenge"]);
+
+    let authorization = client
+        .oauth()
+        .authorize_url(AuthorizationRequest::new("id").pkce(false).state("xyz"))
+        .unwrap();
+    assert_eq!("xyz", authorization.state);
+    assert!(authorization.code_verifier.is_none());
+    assert!(!authorization.url.contains("code_challenge"));
+}
+
+#[tokio::test]
+async fn refresh_token_test() {
+    let (client, _server, exchange) = mock_with_body(
+        "/oauth/access_token",
+        "oauth/refresh-token-success",
+        "POST",
+        Matcher::PartialJsonString(
+            r#"{"grant_type":"refresh_token","refresh_token":"zKQ7OLqF5N1gylcJweA9WodA000BUNJD"}"#
+                .to_string(),
+        ),
+    )
+    .await;
+
+    let payload = OAuthRefreshTokenPayload {
+        client_id: "id".to_string(),
+        client_secret: Some("secret".into()),
+        refresh_token: "zKQ7OLqF5N1gylcJweA9WodA000BUNJD".into(),
+    };
+
+    let access_token = client.oauth().refresh(payload).await.unwrap();
+
+    assert_eq!(
+        "b9Jq2cXk4TzV1mWn8RfYs0PhL3dGe7Ua",
+        access_token.access_token.expose()
+    );
+    assert_eq!(
+        Some("Tg5Hs8Kd2Lq0Wz7Xc4Vb1Nm6Pr3Jy9Fa".into()),
+        access_token.refresh_token
+    );
+    assert_eq!(Some(7200), access_token.expires_in);
+    assert!(access_token.created_at.is_some());
+    assert!(access_token.expires_at().is_some());
+    assert!(!access_token.is_expired());
+    exchange.assert_async().await;
+}
+
+#[tokio::test]
+async fn client_credentials_test() {
+    let (client, _server, exchange) = mock_with_body(
+        "/oauth/access_token",
+        "oauth/access-token-success",
+        "POST",
+        Matcher::PartialJsonString(
+            r#"{"grant_type":"client_credentials","scope":"playbooks:write"}"#.to_string(),
+        ),
+    )
+    .await;
+
+    let payload = OAuthClientCredentialsPayload {
+        client_id: "id".to_string(),
+        client_secret: "secret".into(),
+        scope: Some(Scope::PlaybooksWrite.into()),
+    };
+
+    let access_token = client.oauth().client_credentials(payload).await.unwrap();
+
+    assert_eq!(
+        "zKQ7OLqF5N1gylcJweA9WodA000BUNJD",
+        access_token.access_token.expose()
+    );
+    assert_eq!("Bearer", access_token.token_type);
+    assert_eq!(1, access_token.account_id);
+    exchange.assert_async().await;
+}
+
+#[tokio::test]
+async fn revoke_token_test() {
+    let setup = mock("/oauth/revoke", "oauth/revoke-token-success", "POST").await;
+    let client = setup.0;
+
+    let response = client.oauth().revoke("zKQ7OLqF5N1gylcJweA9WodA000BUNJD").await;
+
+    assert!(response.is_ok());
+    assert_eq!(200, response.unwrap());
+}
+
+#[tokio::test]
+async fn introspect_token_test() {
+    let setup = mock("/oauth/introspect", "oauth/introspect-token-success", "POST").await;
+    let client = setup.0;
+
+    let introspection = client
+        .oauth()
+        .introspect("zKQ7OLqF5N1gylcJweA9WodA000BUNJD")
+        .await
+        .unwrap();
+
+    assert!(introspection.active);
+    let scopes = introspection.scope.unwrap();
+    assert!(scopes.has(Scope::PlaybooksRead));
+    assert!(scopes.has(Scope::PlaybooksWrite));
+    assert_eq!(Some(1), introspection.account_id);
+    assert_eq!(Some(1454977459), introspection.exp);
+    assert!(introspection.expires_at().is_some());
+}
+
+#[test]
+fn access_token_debug_is_redacted_test() {
+    let token: AccessToken = serde_json::from_str(
+        r#"{"access_token":"zKQ7OLqF5N1gylcJweA9WodA000BUNJD","token_type":"Bearer","scope":null,"account_id":1,"refresh_token":"Tg5Hs8Kd2Lq0Wz7Xc4Vb1Nm6Pr3Jy9Fa"}"#,
+    )
+    .unwrap();
+
+    let debug = format!("{token:?}");
+    assert!(!debug.contains("zKQ7OLqF5N1gylcJweA9WodA000BUNJD"));
+    assert!(!debug.contains("Tg5Hs8Kd2Lq0Wz7Xc4Vb1Nm6Pr3Jy9Fa"));
+
+    let json = serde_json::to_string(&token).unwrap();
+    assert!(json.contains("zKQ7OLqF5N1gylcJweA9WodA000BUNJD"));
+}
+
+#[test]
+fn empty_scopes_are_not_sent_test() {
+    let payload = DeviceAuthorizationPayload {
+        client_id: "id".to_string(),
+        scope: Some(Scopes::new()),
+    };
+    let value = serde_json::to_value(&payload).unwrap();
+    assert!(value.get("scope").is_none());
+
+    let payload = DeviceAuthorizationPayload {
+        client_id: "id".to_string(),
+        scope: Some(Scope::ActorsRead.into()),
+    };
+    let value = serde_json::to_value(&payload).unwrap();
+    assert_eq!("actors:read", value["scope"]);
+}
+
+#[test]
+fn access_token_scopes_test() {
+    let mut token: AccessToken = serde_json::from_str(
+        r#"{"access_token":"token","token_type":"Bearer","scope":"playbooks:read actors:read","account_id":1}"#,
+    )
+    .unwrap();
+
+    assert!(token.has_scope(Scope::PlaybooksRead));
+    assert!(!token.has_scope(Scope::PlaybooksWrite));
+
+    token.scope = Some(Scopes::from_iter([Scope::PlaybooksWrite]));
+    assert!(token.has_scope(Scope::PlaybooksWrite));
+
+    token.scope = None;
+    assert!(!token.has_scope(Scope::PlaybooksRead));
+}
+
+#[test]
+fn access_token_expiry_test() {
+    let mut token = AccessToken {
+        access_token: "token".into(),
+        account_id: 1,
+        scope: None,
+        token_type: "Bearer".to_string(),
+        refresh_token: None,
+        expires_in: None,
+        created_at: Some(1454966659),
+    };
+    assert!(token.expires_at().is_none());
+    assert!(!token.is_expired());
+
+    token.expires_in = Some(7200);
+    assert!(token.is_expired());
+
+    // A token about to expire is already treated as expired.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    token.created_at = Some(now);
+    token.expires_in = Some(EXPIRY_MARGIN.as_secs() + 5);
+    assert!(!token.is_expired());
+
+    token.expires_in = Some(EXPIRY_MARGIN.as_secs());
+    assert!(token.is_expired());
+}
+
+async fn device_server(outcomes: &[(usize, &str)]) -> mockito::ServerGuard {
+    let mut server = Server::new_async().await;
+    route(&mut server, "POST", "/oauth/authorize_device", 200, r#"{"device_code":"GmRhmhcxhwAzkoEqiMEg_DnyEysNkuNhszIySk9eS","user_code":"WDJB-MJHT","verification_uri":"https://cloud.amphitheatre.app/device","expires_in":1800,"interval":0}"#)
+        .create_async()
+        .await;
+    route(
+        &mut server,
+        "POST",
+        "/oauth/access_token",
+        400,
+        r#"{"error":"authorization_pending"}"#,
+    )
+    .expect(1)
+    .create_async()
+    .await;
+
+    // Each outcome but the last is answered once, in order.
+    for (i, (status, body)) in outcomes.iter().enumerate() {
+        let mut mock = route(&mut server, "POST", "/oauth/access_token", *status, *body);
+        if i + 1 < outcomes.len() {
+            mock = mock.expect(1);
+        }
+        mock.create_async().await;
+    }
+
+    server
+}
+
+#[tokio::test]
+async fn device_authorization_test() {
+    let server = device_server(&[(
+        200,
+        r#"{"access_token":"zKQ7OLqF5N1gylcJweA9WodA000BUNJD","token_type":"Bearer","scope":null,"account_id":1}"#,
+    )])
+    .await;
+    let client = Client::new(&base_url(&server), None);
+
+    let payload = DeviceAuthorizationPayload {
+        client_id: "id".to_string(),
+        scope: None,
+    };
+    let authorization = client.oauth().authorize_device(payload).await.unwrap();
+
+    assert_eq!("WDJB-MJHT", authorization.user_code);
+    assert_eq!(
+        "https://cloud.amphitheatre.app/device",
+        authorization.verification_uri
+    );
+
+    let access_token = client
+        .oauth()
+        .poll_device_token("id", &authorization)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        "zKQ7OLqF5N1gylcJweA9WodA000BUNJD",
+        access_token.access_token.expose()
+    );
+    assert!(access_token.created_at.is_some());
+}
+
+#[tokio::test]
+async fn device_authorization_denied_test() {
+    let server = device_server(&[(400, r#"{"error":"access_denied"}"#)]).await;
+    let client = Client::new(&base_url(&server), None);
+
+    let payload = DeviceAuthorizationPayload {
+        client_id: "id".to_string(),
+        scope: None,
+    };
+    let authorization = client.oauth().authorize_device(payload).await.unwrap();
+    let result = client.oauth().poll_device_token("id", &authorization).await;
+
+    assert!(matches!(result, Err(Error::OAuth { error, .. }) if error == "access_denied"));
+}
+
+#[tokio::test]
+async fn device_authorization_retries_server_errors_test() {
+    let server = device_server(&[
+        (503, "<html>Service Unavailable</html>"),
+        (
+            200,
+            r#"{"access_token":"zKQ7OLqF5N1gylcJweA9WodA000BUNJD","token_type":"Bearer","scope":null,"account_id":1}"#,
+        ),
+    ])
+    .await;
+    let client = Client::new(&base_url(&server), None);
+
+    let payload = DeviceAuthorizationPayload {
+        client_id: "id".to_string(),
+        scope: None,
+    };
+    let authorization = client.oauth().authorize_device(payload).await.unwrap();
+    let access_token = client
+        .oauth()
+        .poll_device_token("id", &authorization)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        "zKQ7OLqF5N1gylcJweA9WodA000BUNJD",
+        access_token.access_token.expose()
+    );
+}
+
+#[tokio::test]
+async fn device_authorization_rejects_an_unreadable_token_test() {
+    let server = device_server(&[(200, "{}")]).await;
+    let client = Client::new(&base_url(&server), None);
+
+    let payload = DeviceAuthorizationPayload {
+        client_id: "id".to_string(),
+        scope: None,
+    };
+    let authorization = client.oauth().authorize_device(payload).await.unwrap();
+    let result = client.oauth().poll_device_token("id", &authorization).await;
+
+    assert!(matches!(result, Err(Error::Serialization(_))));
+}
+
+/// Plays the part of the browser, following the redirect to the loopback listener.
+///
+/// Like browsers do, it first opens a connection it never sends anything on.
+/// With `forged`, a callback with that state and another code is sent first.
+fn follow_redirect(url: &str, forged: Option<&str>) -> JoinHandle<()> {
+    let url = url::Url::parse(url).unwrap();
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let callback = format!("{}?code=code&state={}", query["redirect_uri"], query["state"]);
+    let forgery = forged.map(|state| format!("{}?code=stolen&state={state}", query["redirect_uri"]));
+    let favicon = query["redirect_uri"].replace("/callback", "/favicon.ico");
+    let address = url::Url::parse(&callback).unwrap().socket_addrs(|| None).unwrap()[0];
+
+    tokio::spawn(async move {
+        let _idle = TcpStream::connect(address).await.unwrap();
+        assert_eq!(404, reqwest::get(favicon).await.unwrap().status().as_u16());
+        if let Some(forgery) = forgery {
+            assert_eq!(400, reqwest::get(forgery).await.unwrap().status().as_u16());
+        }
+        assert_eq!(200, reqwest::get(callback).await.unwrap().status().as_u16());
+    })
+}
+
+#[tokio::test]
+async fn authorize_with_loopback_test() {
+    let setup = mock("/oauth/access_token", "oauth/access-token-success", "POST").await;
+    let client = setup.0;
+    let mut browser = None;
+
+    let access_token = client
+        .oauth()
+        .authorize_with_loopback(
+            AuthorizationRequest::new("id"),
+            None,
+            Duration::from_secs(5),
+            |url| browser = Some(follow_redirect(url, None)),
+        )
+        .await
+        .unwrap();
+    browser.unwrap().await.unwrap();
+
+    assert_eq!(
+        "zKQ7OLqF5N1gylcJweA9WodA000BUNJD",
+        access_token.access_token.expose()
+    );
+}
+
+#[tokio::test]
+async fn authorize_with_loopback_ignores_unknown_state_test() {
+    let (client, _server, exchange) = mock_with_body(
+        "/oauth/access_token",
+        "oauth/access-token-success",
+        "POST",
+        Matcher::PartialJsonString(r#"{"grant_type":"authorization_code","code":"code"}"#.to_string()),
+    )
+    .await;
+    let mut browser = None;
+
+    let access_token = client
+        .oauth()
+        .authorize_with_loopback(
+            AuthorizationRequest::new("id"),
+            None,
+            Duration::from_secs(5),
+            |url| browser = Some(follow_redirect(url, Some("forged"))),
+        )
+        .await
+        .unwrap();
+    browser.unwrap().await.unwrap();
+
+    exchange.assert_async().await;
+    assert_eq!(
+        "zKQ7OLqF5N1gylcJweA9WodA000BUNJD",
+        access_token.access_token.expose()
+    );
+}
+
+#[tokio::test]
+async fn authorize_with_loopback_times_out_test() {
+    let client = Client::new("https://cloud.amphitheatre.app/v1", None);
+
+    let result = client
+        .oauth()
+        .authorize_with_loopback(
+            AuthorizationRequest::new("id"),
+            None,
+            Duration::from_millis(50),
+            |_| {},
+        )
+        .await;
+
+    assert!(matches!(result, Err(Error::Io(err)) if err.kind() == std::io::ErrorKind::TimedOut));
+}
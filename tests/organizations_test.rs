@@ -0,0 +1,125 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amp_client::organizations::{MemberPayload, Role};
+use common::mock;
+
+mod common;
+
+#[tokio::test]
+async fn list_organizations_test() {
+    let setup = mock("/orgs", "organizations/list-organizations-success", "GET").await;
+    let client = setup.0;
+    let orgs = client.organizations().list().await.unwrap();
+
+    assert_eq!(2, orgs.len());
+    assert_eq!("acme", orgs[0].name);
+    assert_eq!(Role::Admin, orgs[0].role);
+    assert_eq!(Role::Member, orgs[1].role);
+}
+
+#[tokio::test]
+async fn get_organization_test() {
+    let setup = mock("/orgs/acme", "organizations/get-organization-success", "GET").await;
+    let client = setup.0;
+    let org = client.organizations().get("acme").await.unwrap();
+
+    assert_eq!(1, org.id);
+    assert_eq!("Acme Inc.", org.title);
+}
+
+#[tokio::test]
+async fn list_members_test() {
+    let setup = mock("/orgs/acme/members", "organizations/list-members-success", "GET").await;
+    let client = setup.0;
+    let members = client.organizations().members("acme").await.unwrap();
+
+    assert_eq!(2, members.len());
+    assert_eq!(Role::Owner, members[0].role);
+    assert_eq!("teammate@example.com", members[1].email);
+}
+
+#[tokio::test]
+async fn add_member_test() {
+    let setup = mock("/orgs/acme/members", "organizations/add-member-created", "POST").await;
+    let client = setup.0;
+    let payload = MemberPayload {
+        email: "newcomer@example.com".to_string(),
+        role: Role::Member,
+    };
+    let member = client.organizations().add_member("acme", payload).await.unwrap();
+
+    assert_eq!(8, member.account_id);
+}
+
+#[tokio::test]
+async fn update_member_test() {
+    let setup = mock(
+        "/orgs/acme/members/7",
+        "organizations/update-member-success",
+        "PATCH",
+    )
+    .await;
+    let client = setup.0;
+    let member = client
+        .organizations()
+        .update_member("acme", 7, Role::Admin)
+        .await
+        .unwrap();
+
+    assert_eq!(Role::Admin, member.role);
+}
+
+#[tokio::test]
+async fn remove_member_test() {
+    let setup = mock(
+        "/orgs/acme/members/7",
+        "organizations/remove-member-success",
+        "DELETE",
+    )
+    .await;
+    let client = setup.0;
+    let status = client.organizations().remove_member("acme", 7).await.unwrap();
+
+    assert_eq!(204, status);
+}
+
+#[tokio::test]
+async fn client_scoped_to_organization_test() {
+    let setup = mock("/orgs/acme/playbooks", "playbooks/list-playbooks-success", "GET").await;
+    let client = setup.0.with_organization("acme");
+    let playbooks = client.playbooks().list(None).await.unwrap();
+
+    assert_eq!(Some("acme"), client.organization());
+    assert_eq!(1, playbooks.len());
+}
+
+#[tokio::test]
+async fn call_scoped_to_organization_test() {
+    let setup = mock(
+        "/orgs/acme/playbooks/1/actors",
+        "actors/list-actors-success",
+        "GET",
+    )
+    .await;
+    let client = setup.0;
+    let actors = client
+        .actors()
+        .in_organization("acme")
+        .list("1", None)
+        .await
+        .unwrap();
+
+    assert_eq!(1, actors.len());
+}
@@ -12,9 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use amp_client::playbooks::PlaybookPayload;
+use amp_client::document::{Format, PlaybookDocument, DOCUMENT_KIND, DOCUMENT_VERSION};
+use amp_client::errors::Error;
+use amp_client::options::{ListOptions, Order, PlaybookSort};
+use amp_client::playbooks::{Applied, PlaybookPayload, PlaybookUpdate};
+use amp_client::timestamp::Timestamp;
 use amp_common::resource::Preface;
-use common::mock;
+use common::{base_url, client, fixture_response, mock, mock_with_body, route};
+use mockito::{Matcher, Server, ServerGuard};
 mod common;
 
 #[tokio::test]
@@ -31,8 +36,8 @@ async fn list_playbooks_test() {
     assert_eq!("a82abba3-df2f-4608-b1a5-9e058ff80468", playbook.id);
     assert_eq!("Untitled", playbook.title);
     assert_eq!(Some("".into()), playbook.description);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.created_at);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.updated_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.created_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.updated_at);
 }
 
 #[tokio::test]
@@ -51,8 +56,8 @@ async fn create_playbook_test() {
     assert_eq!("a82abba3-df2f-4608-b1a5-9e058ff80468", playbook.id);
     assert_eq!("Untitled", playbook.title);
     assert_eq!(Some("".into()), playbook.description);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.created_at);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.updated_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.created_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.updated_at);
 }
 
 #[tokio::test]
@@ -71,8 +76,8 @@ async fn get_playbook_test() {
     assert_eq!("a82abba3-df2f-4608-b1a5-9e058ff80468", playbook.id);
     assert_eq!("Untitled", playbook.title);
     assert_eq!(Some("".into()), playbook.description);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.created_at);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.updated_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.created_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.updated_at);
 }
 
 #[tokio::test]
@@ -97,8 +102,8 @@ async fn update_playbook_test() {
     assert_eq!("a82abba3-df2f-4608-b1a5-9e058ff80468", playbook.id);
     assert_eq!("Untitled", playbook.title);
     assert_eq!(Some("".into()), playbook.description);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.created_at);
-    // assert_eq!("2016-01-19T20:50:26Z", playbook.updated_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.created_at);
+    assert_eq!(Some(Timestamp::from("2016-01-19T20:50:26Z")), playbook.updated_at);
 }
 
 #[tokio::test]
@@ -167,3 +172,497 @@ async fn stop_playbook_test() {
     assert!(response.is_ok());
     assert_eq!(204, response.unwrap());
 }
+
+#[tokio::test]
+async fn list_playbooks_with_options_test() {
+    let mut server = Server::new_async().await;
+    let mock = route(&mut server, "GET", "/playbooks", 200, "[]")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("sort".into(), "updated_at:desc".into()),
+            Matcher::UrlEncoded("title".into(), "Untitled".into()),
+            Matcher::UrlEncoded("page".into(), "2".into()),
+            Matcher::UrlEncoded("per_page".into(), "10".into()),
+        ]))
+        .create_async()
+        .await;
+
+    let client = client(&server);
+    let options = ListOptions::new()
+        .sort(PlaybookSort::UpdatedAt, Order::Desc)
+        .title("Untitled")
+        .page(2)
+        .per_page(10);
+    let playbooks = client.playbooks().list(Some(options)).await.unwrap();
+
+    assert!(playbooks.is_empty());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn list_all_playbooks_follows_the_pagination_test() {
+    let (_, items) = fixture_response("playbooks/list-playbooks-success");
+
+    let mut server = Server::new_async().await;
+    let mut pages = Vec::new();
+    for page in ["1", "2"] {
+        let body = format!(r#"{{"data":{items},"pagination":{{"current_page":{page},"total_pages":2}}}}"#);
+        let mock = route(&mut server, "GET", "/playbooks", 200, &body)
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("page".into(), page.into()),
+                Matcher::UrlEncoded("per_page".into(), "30".into()),
+                Matcher::UrlEncoded("sort".into(), "title:asc".into()),
+            ]))
+            .create_async()
+            .await;
+        pages.push(mock);
+    }
+
+    let client = client(&server);
+    let options = ListOptions::new().sort(PlaybookSort::Title, Order::Asc);
+    let playbooks = client.playbooks().list_all(Some(options)).await.unwrap();
+
+    assert_eq!(2, playbooks.len());
+    for page in pages {
+        page.assert_async().await;
+    }
+}
+
+#[tokio::test]
+async fn list_all_playbooks_follows_link_headers_test() {
+    let (_, items) = fixture_response("playbooks/list-playbooks-success");
+
+    let mut server = Server::new_async().await;
+    let next = format!(r#"<{}/playbooks?page=2>; rel="next""#, base_url(&server));
+    let mut pages = Vec::new();
+    for (page, link) in [("1", Some(next.as_str())), ("2", None)] {
+        let mut mock = route(&mut server, "GET", "/playbooks", 200, &items)
+            .match_query(Matcher::UrlEncoded("page".into(), page.into()));
+        if let Some(link) = link {
+            mock = mock.with_header("link", link);
+        }
+        pages.push(mock.create_async().await);
+    }
+
+    let client = client(&server);
+    let playbooks = client.playbooks().list_all(None).await.unwrap();
+
+    assert_eq!(4, playbooks.len());
+    for page in pages {
+        page.assert_async().await;
+    }
+}
+
+#[tokio::test]
+async fn list_all_playbooks_stops_when_the_server_ignores_the_page_test() {
+    let (_, items) = fixture_response("playbooks/list-playbooks-success");
+
+    let mut server = Server::new_async().await;
+    let mock = route(&mut server, "GET", "/playbooks", 200, &items)
+        .match_query(Matcher::Any)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let client = client(&server);
+    let options = ListOptions::new().per_page(2);
+    let playbooks = client.playbooks().list_all(Some(options)).await.unwrap();
+
+    assert_eq!(2, playbooks.len());
+    mock.assert_async().await;
+}
+
+fn playbook_body() -> String {
+    fixture_response("playbooks/update-playbook-success").1
+}
+
+#[tokio::test]
+async fn partial_update_playbook_test() {
+    let (client, _server, mock) = mock_with_body(
+        "/playbooks/1",
+        "playbooks/update-playbook-success",
+        "PATCH",
+        Matcher::JsonString(r#"{"title":"Untitled"}"#.to_string()),
+    )
+    .await;
+
+    let payload = PlaybookUpdate {
+        title: Some("Untitled".to_string()),
+        ..Default::default()
+    };
+    let playbook = client.playbooks().update("1", payload).await.unwrap();
+
+    assert_eq!("Untitled", playbook.title);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn update_playbook_if_match_test() {
+    let mut server = Server::new_async().await;
+    route(&mut server, "GET", "/playbooks/1", 200, &playbook_body())
+        .match_header("authorization", "Bearer some-token")
+        .match_header("accept", "application/json")
+        .match_header("user-agent", Matcher::Regex("^amp-client-rust/".to_string()))
+        .with_header("etag", r#""v1""#)
+        .create_async()
+        .await;
+    let update = route(&mut server, "PATCH", "/playbooks/1", 200, &playbook_body())
+        .match_header("if-match", r#""v1""#)
+        .match_body(Matcher::JsonString(
+            r#"{"description":"A new description"}"#.to_string(),
+        ))
+        .with_header("etag", r#""v2""#)
+        .create_async()
+        .await;
+
+    let client = client(&server);
+    let current = client.playbooks().get_versioned("1").await.unwrap();
+    assert_eq!(Some(r#""v1""#), current.etag.as_deref());
+    assert_eq!("Untitled", current.value.title);
+
+    let payload = PlaybookUpdate {
+        description: Some("A new description".to_string()),
+        ..Default::default()
+    };
+    let updated = client
+        .playbooks()
+        .update_if_match("1", current.etag.as_deref().unwrap(), payload)
+        .await
+        .unwrap();
+
+    assert_eq!(Some(r#""v2""#), updated.etag.as_deref());
+    update.assert_async().await;
+}
+
+#[tokio::test]
+async fn update_playbook_if_match_conflict_test() {
+    let mut server = Server::new_async().await;
+    route(&mut server, "PATCH", "/playbooks/1", 412, "")
+        .match_header("if-match", r#""v1""#)
+        .with_header("etag", r#""v3""#)
+        .create_async()
+        .await;
+
+    let client = client(&server);
+    let payload = PlaybookUpdate {
+        title: Some("Mine".to_string()),
+        ..Default::default()
+    };
+    let err = client
+        .playbooks()
+        .update_if_match("1", r#""v1""#, payload)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Conflict { etag: Some(etag) } if etag == r#""v3""#));
+}
+
+const MANIFEST: &str = r#"
+live = true
+once = true
+
+[meta]
+name = "amp-example-go"
+version = "0.0.3"
+authors = ["Eguo Wang <wangeguo@gmail.com>"]
+description = "A simple Golang example app"
+readme = "README.md"
+homepage = "https://github.com/amphitheatre-app/amp-example-go"
+repository = "https://github.com/amphitheatre-app/amp-example-go"
+license = "Apache-2.0"
+keywords = ["example", "golang", "getting-started"]
+categories = ["example"]
+
+[partners]
+"#;
+
+#[test]
+fn playbook_payload_from_manifest_test() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join(".amp.toml"), MANIFEST).unwrap();
+
+    let payload = PlaybookPayload::from_manifest(dir.path()).unwrap();
+
+    assert_eq!("amp-example-go", payload.title);
+    assert_eq!("A simple Golang example app", payload.description);
+    assert_eq!("amp-example-go", payload.preface.name);
+    assert!(payload.preface.manifest.is_some());
+}
+
+#[test]
+fn playbook_payload_from_invalid_manifest_test() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("character.toml");
+
+    std::fs::write(&path, "[meta\nname = ").unwrap();
+    assert!(matches!(
+        PlaybookPayload::from_manifest(&path),
+        Err(Error::Manifest(_))
+    ));
+
+    std::fs::write(
+        &path,
+        MANIFEST.replace(r#"name = "amp-example-go""#, r#"name = """#),
+    )
+    .unwrap();
+    assert!(matches!(
+        PlaybookPayload::from_manifest(&path),
+        Err(Error::Validation(_))
+    ));
+
+    let missing = dir.path().join("missing");
+    assert!(matches!(
+        PlaybookPayload::from_manifest(missing),
+        Err(Error::Io(_))
+    ));
+}
+
+#[tokio::test]
+async fn create_playbook_from_manifest_test() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join(".amp.toml"), MANIFEST).unwrap();
+
+    let (client, _server, mock) = mock_with_body(
+        "/playbooks",
+        "playbooks/create-playbook-created",
+        "POST",
+        Matcher::PartialJsonString(
+            r#"{"title":"amp-example-go","description":"A simple Golang example app","preface":{"name":"amp-example-go"}}"#.to_string(),
+        ),
+    )
+    .await;
+
+    let playbook = client.playbooks().create_from_manifest(dir.path()).await.unwrap();
+
+    assert_eq!("a82abba3-df2f-4608-b1a5-9e058ff80468", playbook.id);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn create_playbook_from_repository_test() {
+    let (client, _server, mock) = mock_with_body(
+        "/playbooks",
+        "playbooks/create-playbook-created",
+        "POST",
+        Matcher::PartialJsonString(
+            r#"{"title":"amp-example-go","preface":{"name":"amp-example-go","repository":{"repo":"https://github.com/amphitheatre-app/amp-example-go.git","tag":"v0.0.3","path":"services/api"}}}"#.to_string(),
+        ),
+    )
+    .await;
+
+    let playbook = client
+        .playbooks()
+        .repository("https://github.com/amphitheatre-app/amp-example-go.git")
+        .branch("main")
+        .tag("v0.0.3")
+        .path("./services/api/")
+        .create()
+        .await
+        .unwrap();
+
+    assert_eq!("a82abba3-df2f-4608-b1a5-9e058ff80468", playbook.id);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn create_playbook_from_invalid_repository_test() {
+    let setup = mock("/playbooks", "playbooks/create-playbook-created", "POST").await;
+    let client = setup.0;
+
+    let err = client
+        .playbooks()
+        .repository("not a repository")
+        .create()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Validation(_)));
+
+    let err = client
+        .playbooks()
+        .repository("https://github.com/amphitheatre-app/amp-example-go")
+        .commit("main")
+        .create()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Validation(_)));
+}
+
+async fn apply_server(existing: &str) -> ServerGuard {
+    let mut server = Server::new_async().await;
+    route(&mut server, "GET", "/playbooks", 200, existing)
+        .match_query(Matcher::UrlEncoded("title".into(), "Untitled".into()))
+        .create_async()
+        .await;
+    route(
+        &mut server,
+        "GET",
+        "/playbooks/a82abba3-df2f-4608-b1a5-9e058ff80468",
+        200,
+        &playbook_body(),
+    )
+    .with_header("etag", r#""v1""#)
+    .create_async()
+    .await;
+    server
+}
+
+fn desired(description: &str) -> PlaybookPayload {
+    let playbook: serde_json::Value = serde_json::from_str(&playbook_body()).unwrap();
+    PlaybookPayload {
+        title: "Untitled".to_string(),
+        description: description.to_string(),
+        preface: serde_json::from_value(playbook["preface"].clone()).unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn apply_creates_a_missing_playbook_test() {
+    // Listing filters by a partial title, only the exact one is a match.
+    let other = playbook_body().replace(r#""title":"Untitled""#, r#""title":"Untitled copy""#);
+    let mut server = apply_server(&format!("[{other}]")).await;
+    let create = route(&mut server, "POST", "/playbooks", 201, &playbook_body())
+        .create_async()
+        .await;
+
+    let client = client(&server);
+    let applied = client.playbooks().apply(desired("")).await.unwrap();
+
+    assert!(matches!(applied, Applied::Created(_)));
+    create.assert_async().await;
+}
+
+#[tokio::test]
+async fn apply_leaves_a_matching_playbook_unchanged_test() {
+    let mut server = apply_server(&format!("[{}]", playbook_body())).await;
+    let update = server.mock("PATCH", Matcher::Any).expect(0).create_async().await;
+
+    let client = client(&server);
+    let applied = client.playbooks().apply(desired("")).await.unwrap();
+
+    assert!(matches!(applied, Applied::Unchanged(_)));
+    assert_eq!("a82abba3-df2f-4608-b1a5-9e058ff80468", applied.playbook().id);
+    update.assert_async().await;
+}
+
+#[tokio::test]
+async fn apply_updates_only_the_changed_fields_test() {
+    let mut server = apply_server(&format!("[{}]", playbook_body())).await;
+    let update = route(
+        &mut server,
+        "PATCH",
+        "/playbooks/a82abba3-df2f-4608-b1a5-9e058ff80468",
+        200,
+        &playbook_body(),
+    )
+    .match_header("if-match", r#""v1""#)
+    .match_body(Matcher::JsonString(
+        r#"{"description":"A new description"}"#.to_string(),
+    ))
+    .with_header("etag", r#""v2""#)
+    .create_async()
+    .await;
+
+    let client = client(&server);
+    let applied = client
+        .playbooks()
+        .apply(desired("A new description"))
+        .await
+        .unwrap();
+
+    assert!(matches!(applied, Applied::Updated(_)));
+    update.assert_async().await;
+}
+
+#[tokio::test]
+async fn apply_fails_on_a_concurrent_update_test() {
+    let mut server = apply_server(&format!("[{}]", playbook_body())).await;
+    route(
+        &mut server,
+        "PATCH",
+        "/playbooks/a82abba3-df2f-4608-b1a5-9e058ff80468",
+        412,
+        "",
+    )
+    .match_header("if-match", r#""v1""#)
+    .with_header("etag", r#""v2""#)
+    .create_async()
+    .await;
+
+    let client = client(&server);
+    let err = client
+        .playbooks()
+        .apply(desired("A new description"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Conflict { etag: Some(etag) } if etag == r#""v2""#));
+}
+
+#[tokio::test]
+async fn export_and_import_playbook_test() {
+    let setup = mock(
+        "/playbooks/a82abba3-df2f-4608-b1a5-9e058ff80468",
+        "playbooks/get-playbook-success",
+        "GET",
+    )
+    .await;
+    let client = setup.0;
+
+    for format in [Format::Yaml, Format::Json] {
+        let exported = client
+            .playbooks()
+            .export("a82abba3-df2f-4608-b1a5-9e058ff80468", format)
+            .await
+            .unwrap();
+        assert!(!exported.contains("a82abba3-df2f-4608-b1a5-9e058ff80468"));
+
+        let document = PlaybookDocument::parse(&exported, format).unwrap();
+        assert_eq!(DOCUMENT_KIND, document.kind);
+        assert_eq!(DOCUMENT_VERSION, document.version);
+        assert_eq!("Untitled", document.title);
+        assert_eq!(None, document.description);
+        assert_eq!("amp-example-go", document.preface.name);
+        assert_eq!(1, document.characters.len());
+    }
+
+    let yaml = client
+        .playbooks()
+        .export("a82abba3-df2f-4608-b1a5-9e058ff80468", Format::Yaml)
+        .await
+        .unwrap();
+
+    let (other, _server, create) = mock_with_body(
+        "/playbooks",
+        "playbooks/create-playbook-created",
+        "POST",
+        Matcher::PartialJsonString(
+            r#"{"title":"Untitled","description":"","preface":{"name":"amp-example-go"}}"#.to_string(),
+        ),
+    )
+    .await;
+
+    let playbook = other.playbooks().import(&yaml, Format::Yaml).await.unwrap();
+
+    assert_eq!("Untitled", playbook.title);
+    create.assert_async().await;
+}
+
+#[test]
+fn document_format_from_path_test() {
+    assert_eq!(Some(Format::Yaml), Format::from_path("playbooks/preview.yml"));
+    assert_eq!(Some(Format::Yaml), Format::from_path("playbook.YAML"));
+    assert_eq!(Some(Format::Json), Format::from_path("playbook.json"));
+    assert_eq!(None, Format::from_path("playbook.toml"));
+}
+
+#[test]
+fn document_parse_checks_kind_and_version_test() {
+    let document = "title: Untitled\npreface:\n  name: amp-example-go\n";
+    let parsed = PlaybookDocument::parse(document, Format::Yaml).unwrap();
+    assert_eq!(DOCUMENT_KIND, parsed.kind);
+    assert_eq!(DOCUMENT_VERSION, parsed.version);
+
+    for header in ["kind: Actor\n", "version: 2\n", "version: 0\n"] {
+        let err = PlaybookDocument::parse(&format!("{header}{document}"), Format::Yaml).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)), "{header}");
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::TcpListener;
+use std::path::Path as FsPath;
+
+use amp_client::client::Client;
+use amp_client::errors::Error;
+use amp_client::sync::{LinkPolicy, PayloadBuilder, SyncQueue};
+use amp_common::sync::{EventKinds, Path, Synchronization};
+use mockito::Server;
+use serde_json::Value;
+
+use crate::common::{base_url, mock, route};
+mod common;
+
+fn event(kind: EventKinds, paths: &[&str]) -> Synchronization {
+    Synchronization {
+        kind,
+        paths: paths.iter().map(|p| Path::File(p.to_string())).collect(),
+        attributes: None,
+        payload: None,
+    }
+}
+
+#[test]
+fn sync_queue_collapses_events_for_the_same_path() {
+    let client = Client::new("https://cloud.amphitheatre.app/v1", None);
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let mut queue = SyncQueue::open(dir.path().join("queue.json")).unwrap();
+
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a.txt"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Modify, &["a.txt"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Modify, &["b.txt"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "world", event(EventKinds::Modify, &["a.txt"]))
+        .unwrap();
+    assert_eq!(3, queue.len());
+
+    // Events before a rename are never collapsed into events after it.
+    queue
+        .push(
+            &actors,
+            "1",
+            "hello",
+            event(EventKinds::Rename, &["a.txt", "c.txt"]),
+        )
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Modify, &["a.txt"]))
+        .unwrap();
+    assert_eq!(5, queue.len());
+}
+
+#[test]
+fn sync_queue_does_not_collapse_events_across_a_directory_rename() {
+    let client = Client::new("https://cloud.amphitheatre.app/v1", None);
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("queue.json");
+    let mut queue = SyncQueue::open(&path).unwrap();
+
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a/x"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Rename, &["a", "b"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a/x"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Modify, &["b/x"]))
+        .unwrap();
+
+    let batches = pending(&path);
+    assert_eq!(4, batches.len());
+    assert_eq!(vec!["a/x"], keys(&batches[0]));
+    assert!(matches!(batches[0].kind, EventKinds::Create));
+}
+
+/// Returns the batches stored in the queue file at `path`.
+fn pending(path: &FsPath) -> Vec<Synchronization> {
+    let state: Value = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+    state["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| serde_json::from_value(entry["batch"].clone()).unwrap())
+        .collect()
+}
+
+#[test]
+fn sync_queue_merges_event_kinds() {
+    let client = Client::new("https://cloud.amphitheatre.app/v1", None);
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("queue.json");
+    let mut queue = SyncQueue::open(&path).unwrap();
+
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a.txt"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Modify, &["a.txt"]))
+        .unwrap();
+    let batches = pending(&path);
+    assert_eq!(1, batches.len());
+    assert!(matches!(batches[0].kind, EventKinds::Create));
+
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Remove, &["b.txt"]))
+        .unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["b.txt"]))
+        .unwrap();
+    let batches = pending(&path);
+    assert_eq!(2, batches.len());
+    assert!(matches!(batches[1].kind, EventKinds::Modify));
+
+    // A file created and removed before it was sent never reaches the actor.
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Remove, &["a.txt"]))
+        .unwrap();
+    let batches = pending(&path);
+    assert_eq!(1, batches.len());
+    assert_eq!(vec!["b.txt"], keys(&batches[0]));
+}
+
+#[test]
+fn sync_queue_rebuilds_the_payload_of_reduced_batches() {
+    let client = Client::new("https://cloud.amphitheatre.app/v1", None);
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("workspace");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("a.txt"), "a").unwrap();
+    std::fs::write(root.join("b.txt"), "b").unwrap();
+
+    let path = dir.path().join("queue.json");
+    let mut queue = SyncQueue::open(&path).unwrap();
+    let batch = PayloadBuilder::new(&root, EventKinds::Modify)
+        .path("a.txt")
+        .path("b.txt")
+        .build()
+        .unwrap();
+    queue.push(&actors, "1", "hello", batch).unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Modify, &["a.txt"]))
+        .unwrap();
+
+    let batches = pending(&path);
+    assert_eq!(2, batches.len());
+    assert_eq!(vec!["b.txt"], keys(&batches[0]));
+
+    let payload = batches[0].payload.as_ref().unwrap();
+    let mut archive = tar::Archive::new(payload.as_slice());
+    let archived: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(vec!["b.txt"], archived);
+}
+
+fn keys(batch: &Synchronization) -> Vec<&str> {
+    batch
+        .paths
+        .iter()
+        .map(|p| match p {
+            Path::File(p) | Path::Directory(p) => p.as_str(),
+        })
+        .collect()
+}
+
+#[test]
+fn sync_queue_is_persisted() {
+    let client = Client::new("https://cloud.amphitheatre.app/v1", None);
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("queue.json");
+
+    let mut queue = SyncQueue::open(&path).unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a.txt"]))
+        .unwrap();
+    drop(queue);
+
+    let queue = SyncQueue::open(&path).unwrap();
+    assert_eq!(1, queue.len());
+    assert!(queue.last_error().is_none());
+}
+
+#[tokio::test]
+async fn sync_queue_flushes_in_order() {
+    let setup = mock("/actors/1/hello/sync", "actors/sync-actor-success", "POST").await;
+    let client = setup.0;
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let mut queue = SyncQueue::open(dir.path().join("queue.json")).unwrap();
+
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a.txt"]))
+        .unwrap();
+    let flushed = queue
+        .sync(&actors, "1", "hello", event(EventKinds::Create, &["b.txt"]))
+        .await
+        .unwrap();
+
+    assert_eq!(2, flushed);
+    assert!(queue.is_empty());
+    assert!(queue.last_error().is_none());
+}
+
+#[tokio::test]
+async fn sync_queue_keeps_batches_when_unreachable() {
+    // Nothing listens on a port that was just released, the connection is refused.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+    let client = Client::new(&format!("http://127.0.0.1:{port}/v1"), None);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("queue.json");
+    let mut queue = SyncQueue::open(&path).unwrap();
+
+    let result = queue
+        .sync(
+            &client.actors(),
+            "1",
+            "hello",
+            event(EventKinds::Create, &["a.txt"]),
+        )
+        .await;
+
+    assert!(matches!(result, Err(Error::Request(_))));
+    assert_eq!(1, queue.len());
+    assert!(queue.last_error().is_some());
+    assert_eq!(1, SyncQueue::open(&path).unwrap().len());
+}
+
+#[tokio::test]
+async fn sync_queue_sets_refused_batches_aside() {
+    let mut server = Server::new_async().await;
+    let refused = route(
+        &mut server,
+        "POST",
+        "/actors/1/hello/sync",
+        422,
+        r#"{"message":"invalid payload"}"#,
+    )
+    .expect(2)
+    .create_async()
+    .await;
+    let client = Client::new(&base_url(&server), None);
+
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let mut queue = SyncQueue::open(dir.path().join("queue.json")).unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a.txt"]))
+        .unwrap();
+    let flushed = queue
+        .sync(&actors, "1", "hello", event(EventKinds::Create, &["b.txt"]))
+        .await
+        .unwrap();
+
+    refused.assert_async().await;
+    assert_eq!(0, flushed);
+    assert!(queue.is_empty());
+    assert_eq!(2, queue.rejected().len());
+    assert_eq!(2, queue.take_rejected().unwrap().len());
+    assert!(queue.rejected().is_empty());
+}
+
+#[tokio::test]
+async fn sync_queue_keeps_batches_when_unauthorized() {
+    let mut server = Server::new_async().await;
+    let unauthorized = route(
+        &mut server,
+        "POST",
+        "/actors/1/hello/sync",
+        401,
+        r#"{"message":"invalid token"}"#,
+    )
+    .expect(1)
+    .create_async()
+    .await;
+    let client = Client::new(&base_url(&server), None);
+
+    let actors = client.actors();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("queue.json");
+    let mut queue = SyncQueue::open(&path).unwrap();
+    queue
+        .push(&actors, "1", "hello", event(EventKinds::Create, &["a.txt"]))
+        .unwrap();
+    let result = queue
+        .sync(&actors, "1", "hello", event(EventKinds::Create, &["b.txt"]))
+        .await;
+
+    unauthorized.assert_async().await;
+    assert!(result.is_err());
+    assert_eq!(2, queue.len());
+    assert!(queue.rejected().is_empty());
+    assert!(queue.last_error().is_some());
+    assert_eq!(2, SyncQueue::open(&path).unwrap().len());
+}
+
+#[tokio::test]
+async fn sync_queue_replays_batches_in_their_organization() {
+    let mut server = Server::new_async().await;
+    let personal = route(&mut server, "POST", "/actors/1/hello/sync", 204, "")
+        .expect(1)
+        .create_async()
+        .await;
+    let scoped = route(&mut server, "POST", "/orgs/acme/actors/1/hello/sync", 204, "")
+        .expect(1)
+        .create_async()
+        .await;
+    let client = Client::new(&base_url(&server), None);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("queue.json");
+    let mut queue = SyncQueue::open(&path).unwrap();
+    queue
+        .push(
+            &client.actors().in_organization("acme"),
+            "1",
+            "hello",
+            event(EventKinds::Create, &["a.txt"]),
+        )
+        .unwrap();
+    queue
+        .push(
+            &client.actors(),
+            "1",
+            "hello",
+            event(EventKinds::Create, &["a.txt"]),
+        )
+        .unwrap();
+    assert_eq!(2, queue.len());
+    drop(queue);
+
+    let mut queue = SyncQueue::open(&path).unwrap();
+    let flushed = queue.flush(&client.actors()).await.unwrap();
+
+    scoped.assert_async().await;
+    personal.assert_async().await;
+    assert_eq!(2, flushed);
+    assert!(queue.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn payload_builder_preserves_modes_and_links() {
+    use std::fs;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("scripts")).unwrap();
+    fs::create_dir_all(root.join("empty")).unwrap();
+    fs::write(root.join("scripts/run.sh"), "#!/bin/sh\n").unwrap();
+    fs::set_permissions(root.join("scripts/run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+    symlink("scripts/run.sh", root.join("run")).unwrap();
+
+    let sync = PayloadBuilder::new(root, EventKinds::Create)
+        .links(LinkPolicy::Preserve)
+        .path("scripts")
+        .path("empty")
+        .path("run")
+        .path("gone.txt")
+        .build()
+        .unwrap();
+
+    assert_eq!(4, sync.paths.len());
+
+    let payload = sync.payload.unwrap();
+    let mut archive = tar::Archive::new(payload.as_slice());
+    let mut seen = vec![];
+    for entry in archive.entries().unwrap() {
+        let entry = entry.unwrap();
+        let path = entry
+            .path()
+            .unwrap()
+            .to_string_lossy()
+            .trim_end_matches('/')
+            .to_string();
+        let header = entry.header();
+
+        match path.as_str() {
+            "scripts/run.sh" => assert_eq!(0o755, header.mode().unwrap() & 0o777),
+            "run" => {
+                assert!(header.entry_type().is_symlink());
+                assert_eq!(
+                    "scripts/run.sh",
+                    entry.link_name().unwrap().unwrap().to_str().unwrap()
+                );
+            }
+            "empty" => assert!(header.entry_type().is_dir()),
+            _ => {}
+        }
+        seen.push(path);
+    }
+
+    assert!(seen.contains(&"scripts/run.sh".to_string()));
+    assert!(seen.contains(&"run".to_string()));
+    assert!(seen.contains(&"empty".to_string()));
+    assert!(!seen.contains(&"gone.txt".to_string()));
+}
+
+#[cfg(unix)]
+#[test]
+fn payload_builder_rejects_dangling_links_when_following() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir().unwrap();
+    symlink("missing.txt", dir.path().join("link")).unwrap();
+
+    let result = PayloadBuilder::new(dir.path(), EventKinds::Modify)
+        .links(LinkPolicy::Follow)
+        .path("link")
+        .build();
+    assert!(result.is_err());
+
+    let sync = PayloadBuilder::new(dir.path(), EventKinds::Modify)
+        .links(LinkPolicy::Preserve)
+        .path("link")
+        .build()
+        .unwrap();
+    assert!(sync.payload.is_some());
+}
@@ -0,0 +1,57 @@
+// Copyright (c) The Amphitheatre Authors. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amp_client::scopes::Scope;
+use amp_client::tokens::TokenPayload;
+use common::mock;
+
+mod common;
+
+#[tokio::test]
+async fn list_tokens_test() {
+    let setup = mock("/me/tokens", "tokens/list-tokens-success", "GET").await;
+    let client = setup.0;
+    let tokens = client.tokens().list().await.unwrap();
+
+    assert_eq!(2, tokens.len());
+    assert_eq!("ci", tokens[0].name);
+    assert!(tokens[0].scope.has(Scope::PlaybooksWrite));
+    assert!(tokens[1].expires_at.is_none());
+}
+
+#[tokio::test]
+async fn create_token_test() {
+    let setup = mock("/me/tokens", "tokens/create-token-created", "POST").await;
+    let client = setup.0;
+    let payload = TokenPayload {
+        name: "ci".to_string(),
+        scope: [Scope::PlaybooksRead, Scope::PlaybooksWrite]
+            .into_iter()
+            .collect(),
+        expires_at: Some("2016-12-18T15:19:37Z".into()),
+    };
+    let created = client.tokens().create(payload).await.unwrap();
+
+    assert_eq!(3, created.token.id);
+    assert_eq!("amp_pat_7d3f9a1c5e8b2d4f6a0c", created.secret.expose());
+}
+
+#[tokio::test]
+async fn revoke_token_test() {
+    let setup = mock("/me/tokens/3", "tokens/revoke-token-success", "DELETE").await;
+    let client = setup.0;
+    let status = client.tokens().revoke(3).await.unwrap();
+
+    assert_eq!(204, status);
+}